@@ -1,119 +1,312 @@
-use std::{sync::{Arc, Mutex}, thread::{self, JoinHandle}, time::{Duration, SystemTime}};
+use std::{cmp::Ordering, collections::HashSet, sync::{Arc, Mutex}, thread::{self, JoinHandle}, time::{Duration, Instant, SystemTime}};
 
-use clap::Parser;
-use ratatui::{crossterm::event::{KeyEventKind, KeyModifiers, MouseEventKind}, layout::{Constraint, Layout, Margin, Rect}, style::{Color, Style, Stylize}, text::{Line, Span}, widgets::{Block, Borders, Paragraph}, Terminal};
-use ratatui::crossterm::event::{self, Event, KeyCode};
+use ratatui::{crossterm::event::{KeyEventKind, KeyModifiers, MouseButton, MouseEventKind}, layout::{Constraint, Layout, Margin, Rect}, style::{Color, Style, Stylize}, text::Line, widgets::{Block, Borders, Paragraph}, Terminal};
+use ratatui::crossterm::{event::{self, Event, KeyCode, KeyEvent}, style};
 
-use crate::interface::{Monitor, Process, ProcessMonitor, HEADERS};
+use crate::interface::{Monitor, Process, ProcessDetails, ProcessId, ProcessMonitor, SearchField, SortColumn, TreeOrder, HEADERS};
 use crate::args::Args;
+use crate::keymap::{Action, Key, Keymap, Lookup, Mode};
+use crate::matcher::{MatchOptions, Pattern};
+use crate::query::Query;
+use crate::signal::Signal;
+use crate::theme::{parse_color, Theme};
+use crate::tree::{self, TreeRow};
 use crate::ui::Ui;
 
+// State of the signal picker popup, the row after the common signals is
+// for typing in any signal number
+struct SignalPicker {
+    selected: usize,
+    number: String
+}
+
+enum PickerAction {
+    Pending,
+    Cancel,
+    Send(Signal)
+}
+
+impl SignalPicker {
+    fn new(default_signal: Signal) -> Self {
+        let selected = Signal::COMMON
+            .iter()
+            .position(|&sig| sig == default_signal);
+
+        Self {
+            selected: selected.unwrap_or(Signal::COMMON.len()),
+            number: if selected.is_some() {
+                String::new()
+            } else {
+                default_signal.number().to_string()
+            }
+        }
+    }
+
+    fn options(&self) -> Vec<String> {
+        let mut options = Signal::COMMON
+            .iter()
+            .map(|sig| format!("{} ({})", sig, sig.number()))
+            .collect::<Vec<String>>();
+        options.push(format!("Other: {}", self.number));
+        options
+    }
+
+    fn handle_key(&mut self, key: KeyEvent) -> PickerAction {
+        match key.code {
+            KeyCode::Esc => return PickerAction::Cancel,
+            KeyCode::Up => self.selected = self.selected.saturating_sub(1),
+            KeyCode::Down => self.selected = (self.selected + 1).min(Signal::COMMON.len()),
+            KeyCode::Char(c) if c.is_ascii_digit() => {
+                self.number.push(c);
+                self.selected = Signal::COMMON.len();
+            },
+            KeyCode::Backspace => {
+                let _ = self.number.pop();
+            },
+            KeyCode::Enter => {
+                let signal = match Signal::COMMON.get(self.selected) {
+                    Some(&sig) => Some(sig),
+                    None => self.number
+                        .parse::<i32>()
+                        .ok()
+                        .and_then(Signal::from_number)
+                };
+
+                if let Some(sig) = signal {
+                    return PickerAction::Send(sig);
+                }
+            },
+            _ => ()
+        }
+        PickerAction::Pending
+    }
+}
+
+#[derive(Clone)]
+enum KillAction {
+    Signal(Signal),
+    // A process and all of its descendants, in the order they are listed
+    SignalTree(Signal),
+    Terminate,
+    // Every process with the same command, as found when the window opened
+    SignalByName(Signal)
+}
+
+// A kill waiting on the user to accept it in the confirmation window
+struct Confirmation {
+    procs: Vec<Process>,
+    action: KillAction
+}
+
+impl Confirmation {
+    fn title(&self) -> String {
+        let what = match &self.action {
+            KillAction::Signal(sig) | KillAction::SignalByName(sig) => format!("Send {} to", sig),
+            KillAction::SignalTree(sig) => {
+                return format!("Send {} to this process tree of {}?", sig, self.procs.len())
+            },
+            KillAction::Terminate => String::from("Terminate"),
+        };
+
+        if self.procs.len() == 1 {
+            format!("{} this process?", what)
+        } else {
+            format!("{} these {} processes?", what, self.procs.len())
+        }
+    }
+}
+
 pub struct App {
     should_die: Arc<Mutex<bool>>,
     current_line: usize,
     pointer: usize,
     monitor: Arc<Mutex<Monitor>>,
     threads: Vec<JoinHandle<()>>,
-    highlight_color: Color,
-    background_color: Color,
-    text_color: Color
+    theme: Theme,
+    default_signal: Signal,
+    // Marked processes, kept by id so marks follow a process through
+    // refreshes and re-sorting
+    selected: HashSet<ProcessId>,
+    confirm_kills: bool,
+    tree_order: TreeOrder,
+    tree_view: bool,
+    // Tree view nodes whose children are hidden
+    collapsed: HashSet<ProcessId>,
+    // The detail pane next to the list, and whether it lists the environment
+    show_details: bool,
+    show_environment: bool,
+    confirmation: Option<Confirmation>,
+    // Signals that couldn't be sent and when, shown with the kill progress
+    // for FAILURE_TIMEOUT
+    failures: Vec<(String, Instant)>,
+    sort_column: SortColumn,
+    sort_descending: bool,
+    // Order search results by the sort column instead of by match score
+    sort_matches: bool,
+    // Match the search against the whole command line instead of the command
+    search_cmdline: bool,
+    match_options: MatchOptions,
+    // Print the chosen processes with pick_format instead of killing anything
+    pick: bool,
+    pick_format: String,
+    picked: Vec<Process>,
+    // What the search box starts out with, and what --select-1 and --exit-0
+    // look at before the interface opens
+    initial_query: String,
+    select_one: bool,
+    exit_zero: bool,
+    keymap: Keymap,
+    // None unless vim mode is on
+    mode: Option<Mode>,
+}
+
+// Whether the interface still has to open once the starting search is checked
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Startup {
+    Open,
+    Exit(i32),
 }
 
 impl App {
-    pub fn new() -> Self {
-        let args = Args::parse();
+    pub fn new(args: Args) -> Result<Self, Box<dyn std::error::Error>> {
         if args.show_colors {
             Self::show_colors()
         }
 
+        if let Some(field) = Self::unknown_format_field(&args.format) {
+            return Err(format!("unknown field {{{}}} in --format", field).into());
+        }
+
+        // The two color flags still work, on top of whichever theme is used
+        let mut theme = Theme::load(args.theme.as_deref())?;
+        if let Some(color) = &args.background_color {
+            theme.set_background(parse_color(color).map_err(|err| format!("--background-color: {}", err))?);
+        }
+        if let Some(color) = &args.highlight_color {
+            theme.highlight = parse_color(color).map_err(|err| format!("--highlight-color: {}", err))?;
+        }
+
+        // crossterm leaves out every color while NO_COLOR is set, asking for a
+        // theme or color by name is taken as wanting them anyway
+        if args.theme.is_some() || args.highlight_color.is_some() || args.background_color.is_some() {
+            style::force_color_output(true);
+        }
+
         let mut ret = Self {
             should_die: Arc::new(Mutex::new(false)),
             current_line: 0,
             pointer: 0,
             monitor: Arc::new(
                 Mutex::new(
-                    Monitor::new(
-                        args.update_interval.unwrap_or(3.0),
-                        args.threshold.unwrap_or(0.3),
-                        args.num_matches.unwrap_or(25)
-                    )
+                    args.create_monitor(25)?
                 )
             ),
             threads: Vec::new(),
-            highlight_color: Self::get_matching_color(
-                args.highlight_color.unwrap_or(String::new()),
-                Color::LightBlue
-            ),
-            background_color: Self::get_matching_color(
-                args.background_color.unwrap_or(String::new()),
-                Color::Rgb(0x12, 0x12, 0x12)
-            ),
-            text_color: Color::White,
+            theme,
+            default_signal: args.signal.unwrap_or(Signal::KILL),
+            selected: HashSet::new(),
+            confirm_kills: args.confirm,
+            tree_order: args.tree_order,
+            tree_view: false,
+            collapsed: HashSet::new(),
+            show_details: false,
+            show_environment: false,
+            confirmation: None,
+            failures: Vec::new(),
+            sort_column: args.sort,
+            sort_descending: args.reverse,
+            sort_matches: args.sort_matches,
+            search_cmdline: args.cmdline,
+            match_options: args.match_options(),
+            pick: args.pick,
+            pick_format: args.format,
+            picked: Vec::new(),
+            initial_query: args.query.clone().unwrap_or_default(),
+            select_one: args.select_one,
+            exit_zero: args.exit_zero,
+            keymap: args.keymap.clone(),
+            // A search given up front is usually what's wanted, so start out
+            // moving around in it instead of typing
+            mode: args.vim.then_some(if args.query.is_some() { Mode::Normal } else { Mode::Insert }),
         };
 
-        ret.text_color = match ret.background_color {
-            Color::White => Color::Black,
-            _ => Color::White
-        };
-        
         ret.collect_data();
-        ret
+        Ok(ret)
     }
 
-    fn get_matching_color(color: String, default: Color) -> Color {
-        let colors = vec![
-            Color::Black,
-            Color::Blue,
-            Color::Cyan,
-            Color::DarkGray,
-            Color::Gray,
-            Color::Green,
-            Color::LightBlue,
-            Color::LightCyan,
-            Color::LightGreen,
-            Color::LightMagenta,
-            Color::LightRed,
-            Color::LightYellow,
-            Color::Magenta,
-            Color::Red,
-            Color::White,
-            Color::Yellow
-        ];
+    fn unknown_format_field(template: &str) -> Option<String> {
+        let proc = Process::new();
+
+        template
+            .split('{')
+            .skip(1)
+            .filter_map(|part| part.split_once('}'))
+            .map(|(name, _)| name)
+            .find(|name| proc.get_field(name).is_none())
+            .map(str::to_string)
+    }
+
+    pub fn is_picking(&self) -> bool {
+        self.pick
+    }
 
-        let found = colors
+    // The processes chosen with enter in pick mode, each formatted on its own line
+    pub fn take_picked(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.picked)
             .iter()
-            .find(|&&c| c.to_string().to_lowercase() == color.to_lowercase());
+            .map(|proc| proc.format(&self.pick_format))
+            .collect()
+    }
 
-        if found.is_some() {
-            found.unwrap().clone()
-        } else {
-            Self::get_color_from_hex(color).unwrap_or(default)
+    // Applies --select-1 and --exit-0 to the starting search the way fzf does,
+    // only ever once and before anything is drawn
+    pub fn settle_initial_query(&mut self) -> Startup {
+        if !self.select_one && !self.exit_zero {
+            return Startup::Open;
         }
-    }
+        // A search that doesn't parse is left for the search box to explain
+        let Ok(query) = Query::parse(&self.initial_query, &self.match_options, self.search_cmdline) else {
+            return Startup::Open;
+        };
 
-    fn get_color_from_hex(color: String) -> Option<Color> {
-        if color.split(",").count() == 3 {
-            let codes = color
-                .split(",")
-                .map(|color_code| {
-                    let ret: u8;
-                    if color_code.starts_with("0x") {
-                        let hex_code = color_code.replace("0x", "");
-                        ret = u8::from_str_radix(&hex_code, 16).unwrap_or(0)
-                    } else {
-                        ret = u8::from_str_radix(color_code, 10).unwrap_or(0)
-                    }
-                    ret
-                })
-                .collect::<Vec<u8>>();
-            return Some(
-                Color::Rgb(codes[0], codes[1], codes[2])
-            );
+        let mut mon = self.monitor.lock().unwrap();
+        // The refresh thread may not have read anything yet
+        if mon.get_all_procs().is_none() {
+            mon.get_procs_from_system();
         }
-        None
+        let procs = mon.get_procs_by_query(&query);
+
+        match procs.as_slice() {
+            [] if self.exit_zero => Startup::Exit(1),
+            [proc] if self.select_one && self.pick => {
+                self.picked = vec![proc.clone()];
+                Startup::Exit(0)
+            },
+            // Still asked about, just without having to find it first
+            [_] if self.select_one && self.confirm_kills => {
+                self.confirmation = Some(Confirmation { procs, action: KillAction::Signal(self.default_signal) });
+                Startup::Open
+            },
+            [proc] if self.select_one => match mon.kill_proc(proc, self.default_signal) {
+                Ok(()) => {
+                    println!("sent {} to {} {}", self.default_signal, proc.get_pid(), proc.get_command());
+                    Startup::Exit(0)
+                },
+                Err(err) => {
+                    eprintln!("couldn't send {} to {} {}: {}", self.default_signal, proc.get_pid(), proc.get_command(), err);
+                    Startup::Exit(1)
+                },
+            },
+            _ => Startup::Open,
+        }
+    }
+
+    // Tells the refresh thread to finish up, so join_threads doesn't wait on it
+    pub fn stop(&self) {
+        *self.should_die.lock().unwrap() = true;
     }
 
-    pub fn show_colors() -> () {
+    pub fn show_colors() {
         let colors = vec![
             Color::Black,
             Color::Blue,
@@ -141,14 +334,15 @@ impl App {
             .collect::<Vec<String>>()
             .join(", ")
         );
-        println!("Or you can choose from your own colors using hex values, (e.g. 0xff,0xff,0xff or 255,255,255 for white)");
+        println!("Or you can choose from your own colors using hex values, (e.g. #ffffff, 0xff,0xff,0xff or 255,255,255 for white)");
+        println!("Built-in themes for --theme: {}", Theme::builtin_names().join(", "));
 
         std::process::exit(0);
     }
 
     pub fn join_threads(self) {
         for thread  in self.threads {
-            let _ = thread.join().unwrap();
+            thread.join().unwrap();
         }
     }
 
@@ -180,8 +374,15 @@ impl App {
                         )
                         .saturating_sub(elapsed_time);
                     
-                    if !time_to_sleep.is_zero() {
-                        thread::sleep(time_to_sleep);
+                    // Sleep in short steps so quitting doesn't have to wait
+                    // out the rest of the interval
+                    let wake_time = Instant::now() + time_to_sleep;
+                    while !*die.lock().unwrap() {
+                        let remaining = wake_time.saturating_duration_since(Instant::now());
+                        if remaining.is_zero() {
+                            break;
+                        }
+                        thread::sleep(remaining.min(Duration::from_millis(50)));
                     }
                 }
             }
@@ -189,23 +390,190 @@ impl App {
         self.threads.push(data_thread);
     }
 
+    // The processes an action applies to, every marked process if there are
+    // any and otherwise just the highlighted one
+    fn get_targets(&self, current_process: &Process) -> Vec<Process> {
+        if !self.selected.is_empty() {
+            self.monitor.lock()
+                .unwrap()
+                .get_procs_by_ids(&self.selected)
+        } else if current_process.get_pid() != u64::MAX {
+            vec![current_process.clone()]
+        } else {
+            Vec::new()
+        }
+    }
+
+    // Runs the action right away or opens the confirmation window for it.
+    // Killing more than one process always has to be confirmed, and so does
+    // killing marked processes since the search may be hiding them
+    fn request_action(&mut self, procs: Vec<Process>, action: KillAction) {
+        // Picking never kills anything
+        if procs.is_empty() || self.pick {
+            return;
+        }
+
+        if self.confirm_kills
+            || procs.len() > 1
+            || !self.selected.is_empty()
+            || matches!(action, KillAction::SignalByName(..) | KillAction::SignalTree(_)) {
+            self.confirmation = Some(Confirmation { procs, action });
+        } else {
+            self.perform_action(&procs, action);
+        }
+    }
+
+    fn perform_action(&mut self, procs: &[Process], action: KillAction) {
+        let mut mon = self.monitor.lock().unwrap();
+
+        match action {
+            // Only what the confirmation window showed, taken once when it
+            // opened. A process with the same name that started since then was
+            // never asked about
+            KillAction::Signal(sig) | KillAction::SignalTree(sig) | KillAction::SignalByName(sig) => {
+                let now = Instant::now();
+                self.failures.extend(
+                    mon.kill_proc_list(procs, sig)
+                        .into_iter()
+                        .map(|(proc, err)| (
+                            format!("{} ({}): couldn't send {}: {}", proc.get_command(), proc.get_pid(), sig, err),
+                            now
+                        ))
+                );
+            },
+            KillAction::Terminate => procs
+                .iter()
+                .for_each(|proc| mon.terminate_proc(proc)),
+        }
+        self.selected.clear();
+    }
+
+    // What the search box title says about how searches are matched
+    fn describe_search(&self) -> String {
+        let mut parts = vec![self.match_options.mode.name()];
+        if self.search_cmdline {
+            parts.push("command line");
+        }
+        if self.match_options.case_sensitive {
+            parts.push("case sensitive");
+        }
+        parts.join(", ")
+    }
+
+    // Vim mode shows which mode it is in and any keys of a sequence pressed so far
+    fn search_title(&self, pending_keys: &[Key]) -> String {
+        let title = format!("Current Search ({})", self.describe_search());
+
+        match self.mode {
+            Some(mode) if pending_keys.is_empty() => format!("-- {} -- {}", mode.name(), title),
+            Some(mode) => {
+                let pending = pending_keys
+                    .iter()
+                    .map(|key| key.to_string())
+                    .collect::<String>();
+                format!("-- {} {} -- {}", mode.name(), pending, title)
+            },
+            None => title,
+        }
+    }
+
+    fn compare_procs(&self, first: &Process, second: &Process) -> Ordering {
+        self.sort_column.order(first, second, self.sort_descending)
+    }
+
+    // Clicking the column already sorted by flips the direction
+    fn sort_by_column(&mut self, column: SortColumn) {
+        if self.sort_column == column {
+            self.sort_descending = !self.sort_descending;
+        } else {
+            self.sort_column = column;
+            self.sort_descending = false;
+        }
+    }
+
+    fn toggle_selected(&mut self, proc: &Process) {
+        if !self.selected.remove(&proc.get_id()) {
+            self.selected.insert(proc.get_id());
+        }
+    }
+
+    fn move_down(&mut self, count: usize, num_lines: usize) {
+        let last_line = self.current_line;
+
+        self.current_line = std::cmp::min(
+            self.current_line + 1, 
+            count.saturating_sub(
+                num_lines
+                .saturating_sub(2)
+            )
+        );
+
+        // We didn't move down
+        if self.current_line == last_line {
+            self.pointer = std::cmp::min(
+                self.pointer + 1,
+                count.saturating_sub(
+                    self.current_line
+                    .saturating_add(1)
+                )
+            );
+        }
+    }
+
+    fn move_up(&mut self) {
+        let last_line = self.current_line;
+        self.current_line = 
+            self.current_line.saturating_sub(1);
+
+        // We didn't move up
+        if last_line == self.current_line {
+            self.pointer = 
+                self.pointer.saturating_sub(1);
+        }
+    }
+
     pub fn run<B: ratatui::backend::Backend>(&mut self, terminal: &mut Terminal<B>) -> Result<(), Box<dyn std::error::Error>> {
+        self.run_with_events(terminal, || match event::poll(Duration::from_millis(50)) {
+            Ok(true) => event::read().ok(),
+            _ => None,
+        })
+    }
+
+    // The interface loop, taking its input from next_event rather than the
+    // terminal so tests can drive it. next_event returns None when nothing
+    // happened for a moment
+    fn run_with_events<B, E>(&mut self, terminal: &mut Terminal<B>, mut next_event: E) -> Result<(), Box<dyn std::error::Error>>
+    where
+        B: ratatui::backend::Backend,
+        E: FnMut() -> Option<Event>,
+    {
         let mut show_help = false;
-        let mut search_input = String::new();
+        let mut search_input = self.initial_query.clone();
+        // Why the search box doesn't parse, the last results stay up meanwhile
+        let mut query_error: Option<String> = None;
+        // The last search that parsed, what gets highlighted in the list
+        let mut current_query: Option<Query> = None;
         let mut current_procs: Vec<Process> = Vec::new();
         let mut proc_list_size: usize = 0;
         let mut num_lines: usize = 0;
         let mut current_process: Process = Process::new();
+        let mut signal_picker: Option<SignalPicker> = None;
+        let mut escalation_text: Vec<String> = Vec::new();
+        let mut tree_rows: Vec<TreeRow> = Vec::new();
+        let mut header_rects: Vec<Rect> = Vec::new();
+        // What the detail pane shows, re-read every DETAILS_INTERVAL so it stays live
+        let mut details: Option<(ProcessId, Instant, Result<ProcessDetails, String>)> = None;
         const HEADER_LEN: usize = HEADERS.len();
+        const DETAILS_INTERVAL: Duration = Duration::from_secs(1);
+        const FAILURE_TIMEOUT: Duration = Duration::from_secs(5);
 
-        let keybinds_text = vec![
-            "[ctrl+h] help",
-            "[ctrl+(q|c)] quit",
-            "[ctrl+k] kill process",
-            "[ctrl+b] clear search",
-        ];
+        // Keys pressed in normal mode that start a longer sequence, like the first d of dd
+        let mut pending_keys: Vec<Key> = Vec::new();
 
         loop {
+            let footer_text = self.keymap.hints(self.pick, self.default_signal, false, self.mode);
+            let help_text = self.keymap.hints(self.pick, self.default_signal, true, self.mode);
+
             terminal.draw(|frame| {
                 let current_area = frame.area();
                 proc_list_size = current_area.height.saturating_sub(3) as usize;
@@ -218,9 +586,8 @@ impl App {
                     let block = Block::default()
                             .borders(Borders::NONE)
                             .style(Style::default()
-                            .bg(
-                                self.background_color
-                            ));
+                            .bg(self.theme.background)
+                            .fg(self.theme.text));
 
                     frame.render_widget(block, frame.area());
                     frame.render_widget(p, 
@@ -230,42 +597,97 @@ impl App {
                             Constraint::Percentage(50)
                         )
                     );
-                    return ();
+                    return;
                 }
 
                 // Show the help screen if 'ctrl+h' was pressed
                 if show_help {
-                    Ui::show_help(frame, &keybinds_text, 
-                        self.text_color, self.background_color);
+                    Ui::show_help(frame, &help_text, &self.theme);
                     return;
                 }
                 
-                match self.monitor.try_lock() {
-                    Ok(guard) => {
-                        if search_input.len() > 0 {
-                            current_procs = guard.
-                                get_procs_by_name_fuzzy(&search_input,  
-                                    search_input
-                                    .chars()
-                                    .next()
-                                    .map(|c| c.is_ascii_digit())
-                                    .unwrap_or(false))
-                                .unwrap_or(Vec::new());
-                        } else {
-                            current_procs = guard.get_all_procs()
-                                .unwrap_or(Vec::new());
+                if let Ok(mut guard) = self.monitor.try_lock() {
+                    guard.poll_escalations();
+
+                    // Forget marks on processes that have gone away
+                    if !self.selected.is_empty() {
+                        self.selected = guard.get_procs_by_ids(&self.selected)
+                            .iter()
+                            .map(|proc| proc.get_id())
+                            .collect();
+                    }
+
+                    let now = Instant::now();
+                    escalation_text = guard.get_escalations()
+                        .iter()
+                        .map(|e| e.status_text(now))
+                        .collect();
+                    self.failures.retain(|(_, at)| now.duration_since(*at) < FAILURE_TIMEOUT);
+                    escalation_text.extend(self.failures.iter().map(|(text, _)| text.clone()));
+
+                    // Follows the pointer as of the last frame
+                    if !self.show_details || current_process.get_pid() == u64::MAX {
+                        details = None;
+                    } else if !details.as_ref().is_some_and(|(id, read_at, _)| {
+                        *id == current_process.get_id() && now.duration_since(*read_at) < DETAILS_INTERVAL
+                    }) {
+                        let read = guard.get_proc_details(&current_process)
+                            .map_err(|err| err.to_string());
+                        details = Some((current_process.get_id(), now, read));
+                    }
+
+                    if !search_input.is_empty() {
+                        match Query::parse(&search_input, &self.match_options, self.search_cmdline) {
+                            Ok(query) => {
+                                current_procs = guard.get_procs_by_query(&query);
+                                current_query = Some(query);
+                                query_error = None;
+                            },
+                            Err(err) => query_error = Some(err),
                         }
-                    },
-                    _ => ()
+                    } else {
+                        current_query = None;
+                        query_error = None;
+                        current_procs = guard.get_all_procs()
+                            .unwrap_or_default();
+                    }
+
+                    // Nest everything under its parent, while searching only the
+                    // matches and the processes above them are shown
+                    if self.tree_view {
+                        let all_procs = guard.get_all_procs()
+                            .unwrap_or_default();
+                        let visible = (!search_input.is_empty())
+                            .then(|| tree::with_ancestors(&all_procs, &current_procs));
+
+                        tree_rows = tree::build_rows(&all_procs, visible.as_ref(), &self.collapsed,
+                            |first, second| self.compare_procs(first, second));
+                        current_procs = tree_rows
+                            .iter()
+                            .map(|row| row.proc.clone())
+                            .collect();
+                    }
                 }
 
-                if search_input.len() == 0 {
-                    current_procs
-                    .sort_by(|first, second| {
-                        first.get_command().cmp(second.get_command())
-                    })
+                if !self.tree_view {
+                    tree_rows.clear();
                 }
 
+                // Search results stay ordered by how well they match unless asked otherwise
+                let ranked = current_query
+                    .as_ref()
+                    .is_some_and(|query| !query.get_text().is_empty());
+                let sorted_by_column = self.tree_view || !ranked || self.sort_matches;
+                let highlight = |proc: &Process, field: SearchField| {
+                    current_query
+                        .as_ref()
+                        .map(|query| query.positions(proc, field))
+                        .unwrap_or_default()
+                };
+                if sorted_by_column && !self.tree_view {
+                    current_procs
+                    .sort_by(|first, second| self.compare_procs(first, second))
+                }
 
                 let mut proc_info: Vec<Vec<Line>> = vec![Vec::new(); HEADER_LEN];
 
@@ -275,163 +697,266 @@ impl App {
                     .take(num_lines)
                     .enumerate()
                     .for_each(|(i, proc)|{
-                        if i == self.pointer {
-                            let style = Style::new()
-                                .fg(self.highlight_color);
+                        let style = if i == self.pointer {
                             current_process = proc.clone();
-
-                            proc_info[0].push(
-                                Line::styled(
-                                    proc.get_command(), 
-                                    style.clone()
-                                )
-                            );
-                            proc_info[1].push(
-                                Line::styled(
-                                    proc.get_pid().to_string(),
-                                    style.clone()
-                                )
-                            );
-                            proc_info[2].push(
-                                Line::styled(
-                                    proc.get_mem(),
-                                    style.clone()
-                                )
-                            );
-                            #[cfg(any(target_os = "linux", target_os = "macos"))]
-                            proc_info[3].push(
-                                Line::styled(
-                                    proc.get_cpu(),
-                                    style
-                                )
-                            );
+                            self.theme.highlight_style()
+                        } else if self.selected.contains(&proc.get_id()) {
+                            Style::new().fg(self.theme.selected)
                         } else {
-                            let style = Style::new()
-                                .fg(self.text_color);
-                            proc_info[0].push(
-                                Line::styled(
-                                    proc.get_command(),
-                                    style.clone()
-                                )
-                            );
-                            proc_info[1].push(
-                                Line::styled(
-                                    proc.get_pid().to_string(),
-                                    style.clone()
-                                )
-                            );
-                            proc_info[2].push(
-                                Line::styled(
-                                    proc.get_mem(),
-                                    style.clone()
-                                )
-                            );
-                            #[cfg(any(target_os = "linux", target_os = "macos"))]
-                            proc_info[3].push(
-                                Line::styled(
-                                    proc.get_cpu(),
-                                    style.clone()
-                                )
-                            );
+                            Style::new().fg(self.theme.state_color(proc.get_state()))
+                        };
+                        let tree_row = tree_rows.get(self.current_line + i);
+
+                        #[cfg(target_os = "windows")]
+                        let mem = proc.get_mem().to_string();
+                        #[cfg(any(target_os = "linux", target_os = "macos"))]
+                        let mut mem = format!("{:.1}", proc.get_mem());
+                        #[cfg(any(target_os = "linux", target_os = "macos"))]
+                        let mut cpu = format!("{:.1}", proc.get_cpu());
+
+                        // Parents in the tree view also show the totals for their subtree
+                        #[cfg(any(target_os = "linux", target_os = "macos"))]
+                        if let Some(row) = tree_row.filter(|row| row.has_children) {
+                            mem = format!("{} (\u{03a3} {:.1})", mem, row.subtree_mem);
+                            cpu = format!("{} (\u{03a3} {:.1})", cpu, row.subtree_cpu);
                         }
+
+                        // Only make room for the mark column once something is marked
+                        let mark = if self.selected.is_empty() {
+                            ""
+                        } else if self.selected.contains(&proc.get_id()) {
+                            "\u{25cf} "
+                        } else {
+                            "  "
+                        };
+                        let prefix = format!(
+                            "{}{}",
+                            mark,
+                            tree_row.map(|row| row.prefix.as_str()).unwrap_or("")
+                        );
+
+                        proc_info[0].push(
+                            Ui::highlight_matches(
+                                &prefix,
+                                proc.get_command(),
+                                &highlight(proc, SearchField::Command),
+                                style
+                            )
+                        );
+                        proc_info[1].push(
+                            Line::styled(
+                                proc.get_pid().to_string(),
+                                style
+                            )
+                        );
+                        proc_info[2].push(
+                            Line::styled(
+                                mem,
+                                style
+                            )
+                        );
+                        #[cfg(any(target_os = "linux", target_os = "macos"))]
+                        proc_info[3].push(
+                            Line::styled(
+                                cpu,
+                                style
+                            )
+                        );
+                        proc_info[HEADER_LEN - 1].push(
+                            Ui::highlight_matches(
+                                "",
+                                proc.get_full_command(),
+                                &highlight(proc, SearchField::CommandLine),
+                                style
+                            )
+                        );
                     });
 
                 let block = Block::default()
                         .borders(Borders::ALL)
                         .border_style(Style::default()
-                            .fg(Color::Rgb(0x3a, 0x3a, 0x3a))
+                            .fg(self.theme.border)
                         )
                         .title_alignment(ratatui::layout::Alignment::Center)
                         .title_style(Style::default()
-                            .fg(self.text_color)
+                            .fg(self.theme.header)
                         )
                         .style(Style::default()
-                            .bg(self.background_color)
-                            .fg(self.text_color)
+                            .bg(self.theme.background)
+                            .fg(self.theme.text)
                         );
                 
+                let search_title = if let Some(err) = &query_error {
+                    Line::styled(format!("Invalid query: {}", err), Style::new().fg(self.theme.error))
+                } else {
+                    Line::from(self.search_title(&pending_keys))
+                };
                 let current_search = Paragraph::new(search_input.clone())
-                    .block(block.clone().title("Current Search"));
+                    .block(block.clone().title(search_title));
                 let search_rect = Rect::new(0, proc_list_size as u16, current_area.width / 4, 3);
 
-                let help_text = Paragraph::new(keybinds_text.join("  "))
+                let footer = Paragraph::new(footer_text.join("  "))
                     .block(block.clone().title("Keybinds"))
+                    .fg(self.theme.status)
                     .alignment(ratatui::layout::Alignment::Center);
-                let help_rect = Rect::new(current_area.width / 4, proc_list_size as u16, current_area.width.saturating_sub(current_area.width / 4), 3);
+                let footer_rect = Rect::new(current_area.width / 4, proc_list_size as u16, current_area.width.saturating_sub(current_area.width / 4), 3);
 
-                let proc_list_block = Ui::generate_block(
-                    String::from("Current Processes"),
-                    Some(self.text_color),
-                    Some(self.background_color)
-                )
-                .bg(self.background_color)
-                .fg(self.text_color);
-                let proc_rect =  Rect::new(0, 0, current_area.width, proc_list_size as u16);
+                let proc_list_title = if self.selected.is_empty() {
+                    String::from("Current Processes")
+                } else {
+                    format!("Current Processes ({} marked)", self.selected.len())
+                };
+                let proc_list_block = Ui::generate_block(proc_list_title, &self.theme)
+                    .fg(self.theme.text);
+                let list_rect = Rect::new(0, 0, current_area.width, proc_list_size as u16);
+                // The details take up the right side of the list while they're shown
+                let (proc_rect, details_rect) = if self.show_details {
+                    let [proc_rect, details_rect] = Layout::horizontal([
+                            Constraint::Percentage(60),
+                            Constraint::Percentage(40)
+                        ])
+                        .areas(list_rect);
+                    (proc_rect, Some(details_rect))
+                } else {
+                    (list_rect, None)
+                };
 
                 let inner_proc_rect = proc_rect.inner(Margin::new(1, 1));
                 num_lines = inner_proc_rect.height as usize;
 
                 let proc_rects = Layout::horizontal(
-                        proc_info.iter().map(|_| {
-                            Constraint::Ratio(1, proc_info.len() as u32)
+                        SortColumn::ALL.iter().map(|column| {
+                            Constraint::Fill(column.width())
                         })
                         .collect::<Vec<Constraint>>()
                     )
                     .areas::<HEADER_LEN>(inner_proc_rect);
 
-                frame.render_widget(help_text, help_rect);
+                frame.render_widget(footer, footer_rect);
                 frame.render_widget(proc_list_block, proc_rect);
                 frame.render_widget(current_search, search_rect);
 
+                header_rects = proc_rects.to_vec();
+
                 proc_rects.iter().zip(proc_info).enumerate().for_each(|(i, (rect, info))| {
+                    let title = if sorted_by_column && i == self.sort_column.index() {
+                        format!("{} {}", HEADERS[i], if self.sort_descending { "\u{25bc}" } else { "\u{25b2}" })
+                    } else {
+                        HEADERS[i].to_string()
+                    };
+
                     frame.render_widget(
                         Paragraph::new(info)
                         .block(
-                            block.clone().title(HEADERS[i])
+                            block.clone().title(title)
                         ), 
                         *rect
                     );
                 });
+
+                if let Some(rect) = details_rect {
+                    Ui::show_details(
+                        frame,
+                        rect,
+                        details.as_ref().map(|(_, _, read)| read),
+                        self.show_environment,
+                        self.keymap.key_for(Action::Environment),
+                        &self.theme
+                    );
+                }
+
+                if !escalation_text.is_empty() {
+                    Ui::show_escalations(frame, inner_proc_rect, &escalation_text, &self.theme);
+                }
+
+                if let Some(confirmation) = &self.confirmation {
+                    Ui::show_confirmation(
+                        frame,
+                        confirmation.title(),
+                        &confirmation.procs,
+                        &self.theme
+                    );
+                }
+
+                if let Some(picker) = &signal_picker {
+                    let title = if self.selected.is_empty() {
+                        format!("Send signal to {} ({})", current_process.get_command(), current_process.get_pid())
+                    } else {
+                        format!("Send signal to {} marked processes", self.selected.len())
+                    };
+                    Ui::show_signal_picker(
+                        frame,
+                        title,
+                        &picker.options(),
+                        picker.selected,
+                        &self.theme
+                    );
+                }
             })?;
 
-            if let Ok(true) = event::poll(Duration::from_millis(50)) {
-                if let Ok(event) = event::read() {
-                    match event {
-                        Event::Key(key) =>  {
-                            // Don't render the key event twice
-                            if key.kind != KeyEventKind::Press {
+            if let Some(event) = next_event() {
+                match event {
+                    Event::Key(key) =>  {
+                        // Don't render the key event twice
+                        if key.kind != KeyEventKind::Press {
+                            continue;
+                        }
+
+                        // The confirmation window takes all input while it is open
+                        if let Some(confirmation) = self.confirmation.take() {
+                            match key.code {
+                                KeyCode::Char('y') | KeyCode::Enter => {
+                                    self.perform_action(&confirmation.procs, confirmation.action);
+                                },
+                                KeyCode::Char('n') | KeyCode::Esc => (),
+                                _ => self.confirmation = Some(confirmation)
+                            }
+                            continue;
+                        }
+
+                        // The signal picker takes all input while it is open
+                        if let Some(picker) = signal_picker.as_mut() {
+                            // The key that opened it closes it again
+                            if self.keymap.is_bound(&key, Action::SendSignal) {
+                                signal_picker = None;
                                 continue;
                             }
-                            
-                            // Enable quit, show help, killing process, reset scroll, and clearing the input buffer
-                            if key.modifiers.contains(KeyModifiers::CONTROL) {
-                                match key.code {
-                                    KeyCode::Char('b') => {
-                                        if search_input.len() > 0 {
-                                            self.pointer = 0;
-                                            self.current_line = 0;
-                                        }
-                                        search_input.clear();
-                                    },
-                                    KeyCode::Char('h') => {
-                                        show_help = !show_help
-                                    },
-                                    KeyCode::Char('k') => {
-                                        self.monitor.lock()
-                                            .unwrap()
-                                            .kill_proc(&current_process);
-                                    },
-                                    KeyCode::Char('r') => {
-                                        self.current_line = 0;
-                                        self.pointer = 0;
-                                    },
-                                    KeyCode::Char('q') | KeyCode::Char('c') => {
-                                        *self.should_die.lock().unwrap() = true;
-                                        return Ok(());
-                                    }
-                                    _ => ()
+
+                            match picker.handle_key(key) {
+                                PickerAction::Pending => (),
+                                PickerAction::Cancel => signal_picker = None,
+                                PickerAction::Send(sig) => {
+                                    let targets = self.get_targets(&current_process);
+                                    self.request_action(targets, KillAction::Signal(sig));
+                                    signal_picker = None;
                                 }
-                            } else {
+                            }
+                            continue;
+                        }
+
+                        let action = match self.mode {
+                            Some(Mode::Normal) => match self.keymap.normal_action(&pending_keys, &key, self.pick) {
+                                Lookup::Action(action) => {
+                                    pending_keys.clear();
+                                    Some(action)
+                                },
+                                Lookup::Pending(keys) => {
+                                    pending_keys = keys;
+                                    continue;
+                                },
+                                // Nothing gets typed in normal mode
+                                Lookup::None => {
+                                    pending_keys.clear();
+                                    continue;
+                                },
+                            },
+                            mode => self.keymap.action(&key, self.pick, mode),
+                        };
+
+                        // Keys that aren't bound to anything edit the search,
+                        // unless ctrl is held
+                        let Some(action) = action else {
+                            if !key.modifiers.contains(KeyModifiers::CONTROL) {
                                 match key.code {
                                     KeyCode::Char(char) => {
                                         search_input.push(char);
@@ -443,93 +968,272 @@ impl App {
                                         self.pointer = 0;
                                         self.current_line = 0;
                                     },
-                                    KeyCode::Down => {
-                                        let count = current_procs
-                                            .iter()
-                                            .count();
-
-                                        let last_line = self.current_line;
-
-                                        self.current_line = std::cmp::min(
-                                            self.current_line + 1, 
-                                            count.saturating_sub(
-                                                num_lines
-                                                .saturating_sub(2)
-                                            )
-                                        );
-
-                                        // We didn't move down
-                                        if self.current_line == last_line {
-                                            self.pointer = std::cmp::min(
-                                                self.pointer + 1,
-                                                count.saturating_sub(
-                                                    self.current_line
-                                                    .saturating_add(1)
-                                                )
-                                            );
-                                        }
-                                    },
-                                    KeyCode::Up => {
-                                        let last_line = self.current_line;
-                                        self.current_line = 
-                                            self.current_line.saturating_sub(1);
-
-                                        // We didn't move up
-                                        if last_line == self.current_line {
-                                            self.pointer = 
-                                                self.pointer.saturating_sub(1);
-                                        }
-                                    },
                                     _ => ()
                                 }
                             }
-                        },
-                        Event::Mouse(me) => {
-                            match me.kind {
-                                MouseEventKind::ScrollDown => {
-                                    let count = current_procs
-                                            .iter()
-                                            .count();
-
-                                        let last_line = self.current_line;
-
-                                        self.current_line = std::cmp::min(
-                                            self.current_line + 1, 
-                                            count.saturating_sub(
-                                                num_lines
-                                                .saturating_sub(2)
-                                            )
-                                        );
-
-                                        // We didn't move down
-                                        if self.current_line == last_line {
-                                            self.pointer = std::cmp::min(
-                                                self.pointer + 1,
-                                                count.saturating_sub(
-                                                    self.current_line
-                                                    .saturating_add(1)
-                                                )
-                                            );
-                                        }
-                                },
-                                MouseEventKind::ScrollUp => {
-                                    let last_line = self.current_line;
-                                        self.current_line = 
-                                            self.current_line.saturating_sub(1);
-
-                                        // We didn't move up
-                                        if last_line == self.current_line {
-                                            self.pointer = 
-                                                self.pointer.saturating_sub(1);
-                                        }
+                            continue;
+                        };
+
+                        match action {
+                            Action::ClearSearch => {
+                                if !search_input.is_empty() {
+                                    self.pointer = 0;
+                                    self.current_line = 0;
                                 }
-                                _ => ()
-                            }
+                                search_input.clear();
+                            },
+                            Action::Help => {
+                                show_help = !show_help
+                            },
+                            Action::Kill => {
+                                let targets = self.get_targets(&current_process);
+                                self.request_action(targets, KillAction::Signal(self.default_signal));
+                            },
+                            Action::Terminate => {
+                                let targets = self.get_targets(&current_process);
+                                self.request_action(targets, KillAction::Terminate);
+                            },
+                            Action::ToggleTree => {
+                                self.tree_view = !self.tree_view;
+                                self.current_line = 0;
+                                self.pointer = 0;
+                            },
+                            Action::KillTree if current_process.get_pid() != u64::MAX => {
+                                let tree = self.monitor.lock()
+                                    .unwrap()
+                                    .get_proc_tree(&current_process, self.tree_order);
+                                self.request_action(tree, KillAction::SignalTree(self.default_signal));
+                            },
+                            Action::KillByName if current_process.get_pid() != u64::MAX => {
+                                // Built from the same text the pattern is matched against,
+                                // which leaves out .exe on windows
+                                let name = SearchField::Command.text(&current_process);
+                                let pattern = Pattern::exact(&name);
+                                let procs = self.monitor.lock()
+                                    .unwrap()
+                                    .get_procs_by_pattern(&pattern, SearchField::Command);
+                                self.request_action(procs, KillAction::SignalByName(self.default_signal));
+                            },
+                            Action::MarkAll => {
+                                self.selected.extend(
+                                    current_procs.iter().map(|proc| proc.get_id())
+                                );
+                            },
+                            Action::InvertMarks => {
+                                current_procs
+                                    .iter()
+                                    .for_each(|proc| self.toggle_selected(proc));
+                            },
+                            Action::SendSignal if current_process.get_pid() != u64::MAX || !self.selected.is_empty() => {
+                                signal_picker = Some(SignalPicker::new(self.default_signal));
+                            },
+                            Action::Details => {
+                                self.show_details = !self.show_details;
+                            },
+                            Action::Environment => {
+                                self.show_environment = !self.show_environment;
+                            },
+                            Action::NextSortColumn => {
+                                self.sort_column = self.sort_column.next();
+                            },
+                            Action::ReverseSort => {
+                                self.sort_descending = !self.sort_descending;
+                            },
+                            Action::SortMatches => {
+                                self.sort_matches = !self.sort_matches;
+                            },
+                            Action::SearchCmdline => {
+                                self.search_cmdline = !self.search_cmdline;
+                                self.pointer = 0;
+                                self.current_line = 0;
+                            },
+                            Action::NextSearchMode => {
+                                self.match_options.mode = self.match_options.mode.next();
+                                self.pointer = 0;
+                                self.current_line = 0;
+                            },
+                            Action::ToggleCase => {
+                                self.match_options.case_sensitive = !self.match_options.case_sensitive;
+                            },
+                            Action::ResetScroll => {
+                                self.current_line = 0;
+                                self.pointer = 0;
+                            },
+                            Action::Quit => {
+                                *self.should_die.lock().unwrap() = true;
+                                return Ok(());
+                            },
+                            Action::Pick => {
+                                self.picked = self.get_targets(&current_process);
+                                if !self.picked.is_empty() {
+                                    *self.should_die.lock().unwrap() = true;
+                                    return Ok(());
+                                }
+                            },
+                            Action::CancelPick => {
+                                *self.should_die.lock().unwrap() = true;
+                                return Ok(());
+                            },
+                            Action::MoveDown => {
+                                self.move_down(current_procs.len(), num_lines);
+                            },
+                            Action::MoveUp => {
+                                self.move_up();
+                            },
+                            Action::HalfPageDown => {
+                                for _ in 0..(num_lines / 2).max(1) {
+                                    self.move_down(current_procs.len(), num_lines);
+                                }
+                            },
+                            Action::HalfPageUp => {
+                                for _ in 0..(num_lines / 2).max(1) {
+                                    self.move_up();
+                                }
+                            },
+                            Action::Top => {
+                                self.current_line = 0;
+                                self.pointer = 0;
+                            },
+                            Action::Bottom => {
+                                for _ in 0..current_procs.len() {
+                                    self.move_down(current_procs.len(), num_lines);
+                                }
+                            },
+                            Action::NormalMode => {
+                                self.mode = Some(Mode::Normal);
+                            },
+                            Action::InsertMode => {
+                                self.mode = Some(Mode::Insert);
+                            },
+                            Action::Collapse if self.tree_view => {
+                                self.collapsed.insert(current_process.get_id());
+                            },
+                            Action::Expand if self.tree_view => {
+                                self.collapsed.remove(&current_process.get_id());
+                            },
+                            Action::Mark if current_process.get_pid() != u64::MAX => {
+                                self.toggle_selected(&current_process);
+                                self.move_down(current_procs.len(), num_lines);
+                            },
+                            _ => ()
+                        }
+                    },
+                    Event::Mouse(me) => {
+                        match me.kind {
+                            MouseEventKind::ScrollDown => {
+                                self.move_down(current_procs.len(), num_lines);
+                            },
+                            MouseEventKind::ScrollUp => {
+                                self.move_up();
+                            },
+                            // Clicking a column title sorts by that column
+                            MouseEventKind::Down(MouseButton::Left) => {
+                                let clicked = header_rects
+                                    .iter()
+                                    .position(|rect| {
+                                        me.row == rect.y
+                                            && (rect.x..rect.x + rect.width).contains(&me.column)
+                                    });
+
+                                if let Some(i) = clicked {
+                                    self.sort_by_column(SortColumn::ALL[i]);
+                                }
+                            },
+                            _ => ()
                         }
-                        _ => ()
                     }
+                    _ => ()
                 }
             }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::VecDeque;
+
+    use clap::Parser;
+    use ratatui::backend::TestBackend;
+
+    use super::*;
+
+    fn key(code: KeyCode, modifiers: KeyModifiers) -> Option<Event> {
+        Some(Event::Key(KeyEvent::new(code, modifiers)))
+    }
+
+    // Runs the interface on the sample fixture, feeding it events until they
+    // run out and then quitting. Returns what was on screen at the end, so
+    // the last event should be None to draw once more
+    fn run_on_fixture(extra_args: &[&str], events: Vec<Option<Event>>) -> String {
+        let fixture = concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures/sample.tsv");
+        let args = Args::parse_from(["fzk", "--fixture", fixture].iter().chain(extra_args));
+        let mut app = App::new(args).unwrap();
+
+        // The first sample is taken on the data thread
+        while app.monitor.lock().unwrap().get_all_procs().is_none() {
+            thread::sleep(Duration::from_millis(10));
+        }
+
+        // The list only knows how many rows fit after the first frame, so
+        // start out idle like a real terminal would
+        let mut events = VecDeque::from(events);
+        events.push_front(None);
+        let mut terminal = Terminal::new(TestBackend::new(120, 20)).unwrap();
+        app.run_with_events(&mut terminal, || {
+            events
+                .pop_front()
+                .unwrap_or(key(KeyCode::Char('c'), KeyModifiers::CONTROL))
+        })
+        .unwrap();
+        app.stop();
+        app.join_threads();
+
+        let buffer = terminal.backend().buffer();
+        buffer.content
+            .chunks(buffer.area.width as usize)
+            .map(|row| row.iter().map(|cell| cell.symbol()).collect::<String>())
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+
+    #[test]
+    fn search_narrows_the_list() {
+        let mut events = "nginx"
+            .chars()
+            .map(|c| key(KeyCode::Char(c), KeyModifiers::NONE))
+            .collect::<Vec<Option<Event>>>();
+        events.push(None);
+
+        let screen = run_on_fixture(&[], events);
+        assert!(screen.contains("2000"));
+        assert!(screen.contains("2001"));
+        assert!(!screen.contains("python3"));
+    }
+
+    #[test]
+    fn killing_marked_processes_asks_first() {
+        // Turning down the confirmation leaves the marked process alone, a
+        // kill without one would have removed it
+        let events = vec![
+            key(KeyCode::Tab, KeyModifiers::NONE),
+            key(KeyCode::Char('k'), KeyModifiers::CONTROL),
+            key(KeyCode::Char('n'), KeyModifiers::NONE),
+            None,
+        ];
+
+        let screen = run_on_fixture(&["--query", "python3"], events);
+        assert!(screen.contains("5000"));
+    }
+
+    #[test]
+    fn kill_removes_the_process() {
+        let events = vec![
+            key(KeyCode::Char('k'), KeyModifiers::CONTROL),
+            None,
+        ];
+
+        let screen = run_on_fixture(&["--query", "python3"], events);
+        assert!(!screen.contains("5000"));
+    }
+}
@@ -1,4 +1,8 @@
-use clap::Parser;
+use std::{io, path::PathBuf};
+
+use clap::{Parser, Subcommand, ValueEnum};
+
+use crate::{keymap::Keymap, interface::{CpuMode, FixtureSource, Monitor, ProcessSource, SortColumn, SystemSource, TreeOrder}, matcher::{Algorithm, MatchOptions, SearchMode}, signal::Signal};
 
 #[derive(Parser, Debug)]
 #[command(
@@ -8,23 +12,198 @@ use clap::Parser;
     about = "A TUI app to fuzzy find and kill pesky processes",
     long_about = "This tool helps you find pesky processes using fuzzy search.\nAuthor: Caleb Kornegay <caleb.kornegay@gmail.com>"
 )]
-
 pub struct Args {
-    #[arg(short = 't', long, help="The similarity threshold for matching between 0.0 and 1.0 (default 0.3)")]
+    #[arg(short = 'm', long, value_enum, default_value_t = SearchMode::Fuzzy, global = true, help="How searches are matched against processes")]
+    pub mode: SearchMode,
+
+    #[arg(long, global = true, help="Always match case, otherwise case only matters when the search has an uppercase letter")]
+    pub case_sensitive: bool,
+
+    #[arg(long, global = true, overrides_with = "case_sensitive", help="Ignore case unless the search has an uppercase letter, even if the config file says otherwise")]
+    pub no_case_sensitive: bool,
+
+    #[arg(short = 'a', long, value_enum, default_value_t = Algorithm::Fzf, global = true, help="The matcher used by the fuzzy search mode")]
+    pub algorithm: Algorithm,
+
+    #[arg(short = 't', long, global = true, help="The similarity threshold for the trigram algorithm between 0.0 and 1.0 (default 0.3)")]
     pub threshold: Option<f32>,
 
     #[arg(short = 'i', long, help="The update interval for processes in seconds (default 3, minimum 0.75)")]
     pub update_interval: Option<f32>,
 
-    #[arg(short = 'n', long, help="The maximum number of matches from fuzzy matcher (default 25, minimum 1)")]
+    #[arg(short = 'n', long, global = true, help="The maximum number of matches from fuzzy matcher (default 25, minimum 1)")]
     pub num_matches: Option<usize>,
 
-    #[arg(short = 'c', long, help="The color of the highlighted process (default lightblue)")]
+    #[arg(long, value_name = "THEME", help="The colors of the interface, dark, light, solarized, high-contrast, no-color or a theme file (default dark, or no-color when NO_COLOR is set)")]
+    pub theme: Option<String>,
+
+    #[arg(short = 'c', long, help="The color of the highlighted process, overriding the theme")]
     pub highlight_color: Option<String>,
 
-    #[arg(short = 'b', long, help="The background color of the entire interface (default 0x12, 0x12, 0x12)")]
+    #[arg(short = 'b', long, help="The background color of the entire interface, overriding the theme")]
     pub background_color: Option<String>,
 
-    #[arg(long, help="Show colors")]
-    pub show_colors: bool
+    #[arg(long, value_enum, default_value_t = CpuMode::PerCore, global = true, help="How cpu usage is scaled, per-core (100% is one core) or normalized (100% is every core)")]
+    pub cpu_mode: CpuMode,
+
+    #[arg(short = 's', long, value_name = "SIGNAL", help="The signal sent when killing a process, as a name or number (default KILL)")]
+    pub signal: Option<Signal>,
+
+    #[arg(short = 'g', long, value_name = "SECONDS", help="How long a process gets to exit after SIGTERM before it is sent SIGKILL (default 5)")]
+    pub grace_period: Option<f32>,
+
+    #[arg(long, value_enum, default_value_t = TreeOrder::BottomUp, help="The order processes are signaled in when killing a process tree")]
+    pub tree_order: TreeOrder,
+
+    #[arg(long, value_enum, default_value_t = SortColumn::Command, global = true, help="The column the process list is sorted by")]
+    pub sort: SortColumn,
+
+    #[arg(long, global = true, help="Sort in descending order")]
+    pub reverse: bool,
+
+    #[arg(long, global = true, overrides_with = "reverse", help="Sort in ascending order, even if the config file says otherwise")]
+    pub no_reverse: bool,
+
+    #[arg(long, global = true, help="Sort search results by the sort column instead of by how well they match")]
+    pub sort_matches: bool,
+
+    #[arg(long, global = true, overrides_with = "sort_matches", help="Order search results by how well they match, even if the config file says otherwise")]
+    pub no_sort_matches: bool,
+
+    #[arg(long, help="Ask for confirmation before killing a single process too, killing several always asks")]
+    pub confirm: bool,
+
+    #[arg(long, overrides_with = "confirm", help="Don't ask before killing a single process, even if the config file says otherwise")]
+    pub no_confirm: bool,
+
+    #[arg(long, global = true, help="Match searches against whole command lines instead of commands")]
+    pub cmdline: bool,
+
+    #[arg(long, global = true, overrides_with = "cmdline", help="Match searches against commands, even if the config file says otherwise")]
+    pub no_cmdline: bool,
+
+    #[arg(long, help="Print the selected processes when enter is pressed instead of killing anything")]
+    pub pick: bool,
+
+    #[arg(long, value_name = "FORMAT", default_value = "{pid}", help="How --pick prints each process, using {pid} {ppid} {user} {command} {cmdline} {exe} {state} {mem} {cpu}")]
+    pub format: String,
+
+    #[arg(long, help="Vim style modes, esc switches from typing a search to moving around with j, k, gg, G and acting with dd, x and /")]
+    pub vim: bool,
+
+    #[arg(long, overrides_with = "vim", help="Turn vim mode off, even if the config file turns it on")]
+    pub no_vim: bool,
+
+    #[arg(short = 'q', long, value_name = "QUERY", help="Start with this search already entered")]
+    pub query: Option<String>,
+
+    #[arg(short = '1', long = "select-1", help="If exactly one process matches the starting search act on it right away, printing it with --pick or killing it otherwise")]
+    pub select_one: bool,
+
+    #[arg(short = '0', long = "exit-0", help="Exit right away with status 1 if nothing matches the starting search")]
+    pub exit_zero: bool,
+
+    #[arg(long, help="Show the color names and themes that can be used")]
+    pub show_colors: bool,
+
+    // For tests and demos, see FixtureSource
+    #[arg(long, value_name = "PATH", global = true, hide = true, help="Load processes from a tab separated fixture file instead of the system")]
+    pub fixture: Option<PathBuf>,
+
+    #[arg(long, value_name = "PATH", global = true, help="Read defaults from this file instead of $XDG_CONFIG_HOME/fzk/config.toml")]
+    pub config: Option<PathBuf>,
+
+    // Only set from the [keybinds] table of the config file
+    #[arg(skip)]
+    pub keymap: Keymap,
+
+    #[command(subcommand)]
+    pub command: Option<Command>
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    // Print matching processes instead of opening the interface
+    #[command(about = "Print the processes matching a search and exit")]
+    List(ListArgs),
+    // Signal matching processes without opening the interface
+    #[command(
+        about = "Signal the processes matching a search and exit",
+        after_help = "Exit codes: 0 everything was signaled, 1 error, 2 bad usage, 3 nothing matched, 4 several processes matched without --all or --first, 5 permission denied, 6 only some processes were signaled"
+    )]
+    Kill(KillArgs),
+}
+
+#[derive(clap::Args, Debug)]
+pub struct ListArgs {
+    #[arg(help="Only list processes matching this search, written like in the search box")]
+    pub query: Vec<String>,
+
+    #[arg(short = 'o', long, value_enum, default_value_t = OutputFormat::Table, help="How the processes are printed")]
+    pub output: OutputFormat,
+
+    #[arg(short = 'C', long, value_delimiter = ',', help="Comma separated columns out of pid, ppid, user, command, cmdline, exe, state, mem and cpu")]
+    pub columns: Option<Vec<String>>,
+
+    #[arg(long, help="Leave out the header row of tables and CSV")]
+    pub no_header: bool,
+
+    #[arg(long, value_name = "SECONDS", default_value_t = 0.5, help="How long to measure cpu usage over, 0 reports the average since each process started")]
+    pub delay: f32,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct KillArgs {
+    #[arg(required = true, help="The processes to signal, written like in the search box")]
+    pub query: Vec<String>,
+
+    #[arg(short = 's', long, value_name = "SIGNAL", help="The signal to send, as a name or number (default TERM)")]
+    pub signal: Option<Signal>,
+
+    #[arg(long, conflicts_with = "first", help="Signal every matching process")]
+    pub all: bool,
+
+    #[arg(long, help="Signal only the best match")]
+    pub first: bool,
+
+    #[arg(long, help="Print what would be signaled without signaling anything")]
+    pub dry_run: bool,
+
+    #[arg(short = 'y', long, help="Don't ask for confirmation")]
+    pub yes: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    // Columns padded to line up
+    Table,
+    Json,
+    Csv,
+}
+
+impl Args {
+    pub fn match_options(&self) -> MatchOptions {
+        MatchOptions {
+            mode: self.mode,
+            algorithm: self.algorithm,
+            threshold: self.threshold.unwrap_or(MatchOptions::default().threshold).clamp(0.0, 1.0),
+            case_sensitive: self.case_sensitive,
+        }
+    }
+
+    // default_num is how many matches a search returns unless -n says otherwise
+    pub fn create_monitor(&self, default_num: usize) -> io::Result<Monitor> {
+        let source: Box<dyn ProcessSource> = match &self.fixture {
+            Some(path) => Box::new(FixtureSource::load(path)?),
+            None => Box::new(SystemSource {}),
+        };
+
+        Ok(Monitor::new(
+            self.update_interval.unwrap_or(3.0),
+            self.num_matches.unwrap_or(default_num),
+            self.cpu_mode,
+            self.grace_period.unwrap_or(5.0),
+            source
+        ))
+    }
 }
@@ -0,0 +1,289 @@
+use std::{error::Error, io::{self, BufRead, IsTerminal, Write}, process, thread, time::Duration};
+
+use serde_json::Value;
+
+use crate::args::{Args, KillArgs, ListArgs, OutputFormat};
+use crate::interface::{Monitor, Process, ProcessMonitor};
+use crate::query::Query;
+use crate::signal::Signal;
+
+// The subcommands that print to stdout instead of opening the interface
+
+// Exit codes for kill, 2 is left to clap for bad usage
+pub const EXIT_ERROR: i32 = 1;
+pub const EXIT_NO_MATCH: i32 = 3;
+pub const EXIT_AMBIGUOUS: i32 = 4;
+pub const EXIT_PERMISSION_DENIED: i32 = 5;
+pub const EXIT_PARTIAL: i32 = 6;
+
+#[cfg(target_os = "windows")]
+const DEFAULT_COLUMNS: [&str; 4] = ["pid", "user", "mem", "command"];
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+const DEFAULT_COLUMNS: [&str; 5] = ["pid", "user", "mem", "cpu", "command"];
+
+pub fn list(args: &Args, list: &ListArgs) -> Result<(), Box<dyn Error>> {
+    let columns = match &list.columns {
+        Some(columns) => columns.iter().map(|col| col.trim().to_string()).collect(),
+        None => DEFAULT_COLUMNS.iter().map(|col| col.to_string()).collect::<Vec<String>>(),
+    };
+    let blank = Process::new();
+    if let Some(col) = columns.iter().find(|col| blank.get_field(col).is_none()) {
+        return Err(format!("unknown column '{}'", col).into());
+    }
+
+    // Scripts want every match, not just what fits on a screen
+    let mut monitor = args.create_monitor(usize::MAX)?;
+    sample(&mut monitor, list.delay);
+    let procs = find_procs(args, &monitor, &list.query.join(" "))?;
+
+    let mut out = io::stdout().lock();
+    let res = match list.output {
+        OutputFormat::Table => write_table(&mut out, &procs, &columns, !list.no_header),
+        OutputFormat::Json => write_json(&mut out, &procs, &columns),
+        OutputFormat::Csv => write_csv(&mut out, &procs, &columns, !list.no_header),
+    };
+
+    match res {
+        // Piped into something like head that stopped reading, which is fine
+        Err(err) if err.kind() == io::ErrorKind::BrokenPipe => Ok(()),
+        res => Ok(res?),
+    }
+}
+
+// Returns the exit code, anything that went wrong with a process is reported
+// on stderr rather than as an error
+pub fn kill(args: &Args, kill: &KillArgs) -> Result<i32, Box<dyn Error>> {
+    let signal = kill.signal.unwrap_or(Signal::TERM);
+    let search = kill.query.join(" ");
+
+    let mut monitor = args.create_monitor(usize::MAX)?;
+    monitor.get_procs_from_system();
+    let mut procs = find_procs(args, &monitor, &search)?;
+    // Never take ourselves down along with the matches
+    procs.retain(|proc| proc.get_pid() != u64::from(process::id()));
+
+    if procs.is_empty() {
+        eprintln!("no processes match '{}'", search);
+        return Ok(EXIT_NO_MATCH);
+    }
+
+    if procs.len() > 1 && !kill.all && !kill.first {
+        eprintln!("{} processes match '{}':", procs.len(), search);
+        procs.iter().for_each(|proc| eprintln!("  {}", describe(proc)));
+        eprintln!("pass --all to signal every one of them or --first for the best match");
+        return Ok(EXIT_AMBIGUOUS);
+    }
+    if kill.first {
+        procs.truncate(1);
+    }
+
+    if kill.dry_run {
+        procs
+            .iter()
+            .for_each(|proc| println!("would send {} to {}", signal, describe(proc)));
+        return Ok(0);
+    }
+
+    if !kill.yes && !confirm(signal, &procs)? {
+        eprintln!("nothing was signaled");
+        return Ok(EXIT_ERROR);
+    }
+
+    let mut sent = 0;
+    let mut failed = 0;
+    let mut denied = 0;
+    for proc in &procs {
+        match monitor.kill_proc(proc, signal) {
+            Ok(()) => {
+                sent += 1;
+                println!("sent {} to {}", signal, describe(proc));
+            },
+            Err(err) => {
+                failed += 1;
+                if err.kind() == io::ErrorKind::PermissionDenied {
+                    denied += 1;
+                }
+                eprintln!("couldn't send {} to {}: {}", signal, describe(proc), err);
+            },
+        }
+    }
+
+    Ok(match (sent, failed) {
+        (_, 0) => 0,
+        (0, _) if denied == failed => EXIT_PERMISSION_DENIED,
+        (0, _) => EXIT_ERROR,
+        _ => EXIT_PARTIAL,
+    })
+}
+
+fn describe(proc: &Process) -> String {
+    format!("{} {} ({})", proc.get_pid(), proc.get_command(), proc.get_full_command())
+}
+
+// Lists what is about to be signaled and asks on stdin, which has to be a
+// terminal so a script can't end up signaling things by accident
+fn confirm(signal: Signal, procs: &[Process]) -> Result<bool, String> {
+    if !io::stdin().is_terminal() {
+        return Err(String::from("refusing to signal anything without --yes when stdin isn't a terminal"));
+    }
+
+    procs.iter().for_each(|proc| eprintln!("  {}", describe(proc)));
+    eprint!("Send {} to {} process{}? [y/N] ", signal, procs.len(), if procs.len() == 1 { "" } else { "es" });
+
+    let mut answer = String::new();
+    io::stdin()
+        .lock()
+        .read_line(&mut answer)
+        .map_err(|err| err.to_string())?;
+    Ok(matches!(answer.trim().to_ascii_lowercase().as_str(), "y" | "yes"))
+}
+
+// Reads the processes, twice with delay in between so cpu usage covers
+// that stretch instead of each process's whole lifetime
+fn sample(monitor: &mut Monitor, delay: f32) {
+    monitor.get_procs_from_system();
+
+    if delay > 0.0 {
+        thread::sleep(Duration::from_secs_f32(delay));
+        monitor.get_procs_from_system();
+    }
+}
+
+// The processes matching search in the same order the interface shows them
+fn find_procs(args: &Args, monitor: &Monitor, search: &str) -> Result<Vec<Process>, String> {
+    let query = Query::parse(search, &args.match_options(), args.cmdline)
+        .map_err(|err| format!("invalid query: {}", err))?;
+    let mut procs = monitor.get_procs_by_query(&query);
+
+    if query.get_text().is_empty() || args.sort_matches {
+        procs.sort_by(|first, second| args.sort.order(first, second, args.reverse));
+    }
+    Ok(procs)
+}
+
+fn write_table(out: &mut impl Write, procs: &[Process], columns: &[String], header: bool) -> io::Result<()> {
+    let mut rows = procs
+        .iter()
+        .map(|proc| {
+            columns
+                .iter()
+                .map(|col| proc.get_field(col).unwrap_or_default())
+                .collect::<Vec<String>>()
+        })
+        .collect::<Vec<Vec<String>>>();
+    if header {
+        rows.insert(0, columns.iter().map(|col| col.to_uppercase()).collect());
+    }
+
+    let widths = (0..columns.len())
+        .map(|i| {
+            rows.iter()
+                .map(|row| row[i].chars().count())
+                .max()
+                .unwrap_or(0)
+        })
+        .collect::<Vec<usize>>();
+
+    for row in rows {
+        let line = row
+            .iter()
+            .zip(&widths)
+            .map(|(value, &width)| format!("{:<width$}", value, width = width))
+            .collect::<Vec<String>>()
+            .join("  ");
+        writeln!(out, "{}", line.trim_end())?;
+    }
+    Ok(())
+}
+
+// Written by hand rather than through a map so the keys keep the order the
+// columns were given in
+fn write_json(out: &mut impl Write, procs: &[Process], columns: &[String]) -> io::Result<()> {
+    if procs.is_empty() {
+        return writeln!(out, "[]");
+    }
+
+    writeln!(out, "[")?;
+    for (i, proc) in procs.iter().enumerate() {
+        let fields = columns
+            .iter()
+            .map(|col| format!("{}: {}", Value::from(col.as_str()), json_value(proc, col)))
+            .collect::<Vec<String>>()
+            .join(", ");
+        let comma = if i + 1 < procs.len() { "," } else { "" };
+        writeln!(out, "  {{{}}}{}", fields, comma)?;
+    }
+    writeln!(out, "]")
+}
+
+// Numbers stay numbers so scripts don't have to parse them
+fn json_value(proc: &Process, column: &str) -> Value {
+    match column {
+        "pid" => Value::from(proc.get_pid()),
+        "ppid" => Value::from(proc.get_ppid()),
+        #[cfg(any(target_os = "linux", target_os = "macos"))]
+        "mem" => Value::from(proc.get_displayed_mem()),
+        #[cfg(any(target_os = "linux", target_os = "macos"))]
+        "cpu" => Value::from(proc.get_displayed_cpu()),
+        _ => Value::from(proc.get_field(column).unwrap_or_default()),
+    }
+}
+
+fn write_csv(out: &mut impl Write, procs: &[Process], columns: &[String], header: bool) -> io::Result<()> {
+    if header {
+        writeln!(out, "{}", columns.join(","))?;
+    }
+
+    for proc in procs {
+        let line = columns
+            .iter()
+            .map(|col| csv_escape(&proc.get_field(col).unwrap_or_default()))
+            .collect::<Vec<String>>()
+            .join(",");
+        writeln!(out, "{}", line)?;
+    }
+    Ok(())
+}
+
+fn csv_escape(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::Parser;
+
+    use super::*;
+    use crate::args::Command;
+
+    // Runs kill on the sample fixture, where root's processes refuse signals
+    fn kill_code(argv: &[&str]) -> i32 {
+        let fixture = concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures/sample.tsv");
+        let args = Args::parse_from(["fzk", "--fixture", fixture, "kill"].iter().chain(argv));
+        let Some(Command::Kill(kill_args)) = &args.command else {
+            unreachable!();
+        };
+        kill(&args, kill_args).unwrap()
+    }
+
+    #[test]
+    fn kill_exit_codes() {
+        assert_eq!(kill_code(&["--yes", "name=python3"]), 0);
+        assert_eq!(kill_code(&["--yes", "name=nosuch"]), EXIT_NO_MATCH);
+        assert_eq!(kill_code(&["--yes", "name=bash"]), EXIT_AMBIGUOUS);
+        assert_eq!(kill_code(&["--yes", "name=systemd"]), EXIT_PERMISSION_DENIED);
+        assert_eq!(kill_code(&["--yes", "--all", "pid<1201"]), EXIT_PARTIAL);
+    }
+
+    #[test]
+    fn kill_first_and_dry_run() {
+        assert_eq!(kill_code(&["--yes", "--first", "name=bash"]), 0);
+        // Without --yes this would refuse, stdin isn't a terminal under test
+        assert_eq!(kill_code(&["--dry-run", "--all", "name=bash"]), 0);
+        assert_eq!(kill_code(&["--dry-run", "name=bash"]), EXIT_AMBIGUOUS);
+    }
+}
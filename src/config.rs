@@ -0,0 +1,268 @@
+use std::{collections::BTreeMap, env, fs, io, path::{Path, PathBuf}};
+
+use clap::{parser::ValueSource, ArgMatches, ValueEnum};
+use serde::Deserialize;
+
+use crate::args::{Args, Command};
+use crate::keymap::Keymap;
+use crate::signal::Signal;
+
+// Defaults for the command line options, read from
+// $XDG_CONFIG_HOME/fzk/config.toml or the file given with --config. Keys are
+// named after the long flags and anything given on the command line wins:
+//
+//     mode = "substring"
+//     sort = "cpu"
+//     reverse = true
+//     signal = "TERM"
+//     update-interval = 1.5
+//     theme = "solarized"
+//     columns = ["pid", "user", "cpu", "cmdline"]
+//
+//     [keybinds]
+//     kill = "ctrl+d"
+//     quit = ["ctrl+q", "esc"]
+//     clear-search = []
+//     mark = "space"
+//
+//     [normal-keybinds]
+//     kill = "dk"
+//
+// Enums are kept as strings here and parsed like clap parses the flags, so
+// a bad value gets the same list of possible values either way.
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Config {
+    mode: Option<String>,
+    case_sensitive: Option<bool>,
+    algorithm: Option<String>,
+    threshold: Option<f32>,
+    update_interval: Option<f32>,
+    num_matches: Option<usize>,
+    // A built-in theme, a file in the themes directory or a path, see Theme::load
+    theme: Option<String>,
+    highlight_color: Option<String>,
+    background_color: Option<String>,
+    cpu_mode: Option<String>,
+    signal: Option<SignalValue>,
+    grace_period: Option<f32>,
+    tree_order: Option<String>,
+    sort: Option<String>,
+    reverse: Option<bool>,
+    sort_matches: Option<bool>,
+    confirm: Option<bool>,
+    cmdline: Option<bool>,
+    format: Option<String>,
+    // The columns fzk list prints when -C isn't given
+    columns: Option<Vec<String>>,
+    vim: Option<bool>,
+    // Action names to the keys that trigger them, see Action::name
+    keybinds: BTreeMap<String, Keys>,
+    // The same for normal mode with --vim, which also takes sequences like "gg"
+    normal_keybinds: BTreeMap<String, Keys>,
+}
+
+// Either a single key or a list of them, an empty list unbinds the action
+#[derive(Deserialize)]
+#[serde(untagged, expecting = "a key like \"ctrl+k\" or a list of keys")]
+enum Keys {
+    One(String),
+    Many(Vec<String>),
+}
+
+// Signals can be written either way, signal = "TERM" or signal = 15
+#[derive(Deserialize)]
+#[serde(untagged, expecting = "a signal name or number")]
+enum SignalValue {
+    Name(String),
+    Number(i32),
+}
+
+impl Config {
+    // Reads the file at path, or the default location if there is none. Only
+    // a missing default file is fine, one passed with --config has to exist
+    pub fn load(path: Option<&Path>) -> Result<Self, String> {
+        let (path, required) = match path {
+            Some(path) => (path.to_path_buf(), true),
+            None => match Self::default_path() {
+                Some(path) => (path, false),
+                None => return Ok(Self::default()),
+            },
+        };
+
+        let contents = match fs::read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(err) if err.kind() == io::ErrorKind::NotFound && !required => return Ok(Self::default()),
+            Err(err) => return Err(format!("{}: {}", path.display(), err)),
+        };
+
+        toml::from_str(&contents)
+            .map_err(|err| format!("{}: {}", path.display(), err.to_string().trim_end()))
+    }
+
+    fn default_path() -> Option<PathBuf> {
+        Self::default_dir().map(|dir| dir.join("config.toml"))
+    }
+
+    // Where the config file and the themes directory live
+    #[cfg(any(target_os = "linux", target_os = "macos"))]
+    pub fn default_dir() -> Option<PathBuf> {
+        let base = env::var_os("XDG_CONFIG_HOME")
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| env::var_os("HOME").map(|home| Path::new(&home).join(".config")))?;
+        Some(base.join("fzk"))
+    }
+
+    #[cfg(target_os = "windows")]
+    pub fn default_dir() -> Option<PathBuf> {
+        let base = env::var_os("XDG_CONFIG_HOME")
+            .filter(|dir| !dir.is_empty())
+            .or_else(|| env::var_os("APPDATA"))?;
+        Some(Path::new(&base).join("fzk"))
+    }
+
+    // Fills in every option that wasn't given on the command line. matches
+    // are what args were parsed from, to tell flags apart from clap defaults
+    pub fn apply(self, args: &mut Args, matches: &ArgMatches) -> Result<(), String> {
+        let given = |id: &str| matches.value_source(id) == Some(ValueSource::CommandLine);
+
+        if let (Some(mode), false) = (&self.mode, given("mode")) {
+            args.mode = parse_enum("mode", mode)?;
+        }
+        if let (Some(algorithm), false) = (&self.algorithm, given("algorithm")) {
+            args.algorithm = parse_enum("algorithm", algorithm)?;
+        }
+        if let (Some(cpu_mode), false) = (&self.cpu_mode, given("cpu_mode")) {
+            args.cpu_mode = parse_enum("cpu-mode", cpu_mode)?;
+        }
+        if let (Some(tree_order), false) = (&self.tree_order, given("tree_order")) {
+            args.tree_order = parse_enum("tree-order", tree_order)?;
+        }
+        if let (Some(sort), false) = (&self.sort, given("sort")) {
+            args.sort = parse_enum("sort", sort)?;
+        }
+        if let (Some(format), false) = (self.format, given("format")) {
+            args.format = format;
+        }
+
+        if args.signal.is_none() {
+            args.signal = match self.signal {
+                Some(SignalValue::Name(name)) => Some(parse_signal(&name)?),
+                Some(SignalValue::Number(number)) => Some(parse_signal(&number.to_string())?),
+                None => None,
+            };
+        }
+
+        args.threshold = args.threshold.or(self.threshold);
+        args.update_interval = args.update_interval.or(self.update_interval);
+        args.num_matches = args.num_matches.or(self.num_matches);
+        args.grace_period = args.grace_period.or(self.grace_period);
+        args.theme = args.theme.take().or(self.theme);
+        args.highlight_color = args.highlight_color.take().or(self.highlight_color);
+        args.background_color = args.background_color.take().or(self.background_color);
+
+        // Each of these has a --no- flag to turn off what the file turns on
+        let flag = |on: bool, off: bool, file: Option<bool>| on || (!off && file.unwrap_or(false));
+        args.case_sensitive = flag(args.case_sensitive, args.no_case_sensitive, self.case_sensitive);
+        args.reverse = flag(args.reverse, args.no_reverse, self.reverse);
+        args.sort_matches = flag(args.sort_matches, args.no_sort_matches, self.sort_matches);
+        args.confirm = flag(args.confirm, args.no_confirm, self.confirm);
+        args.cmdline = flag(args.cmdline, args.no_cmdline, self.cmdline);
+        args.vim = flag(args.vim, args.no_vim, self.vim);
+
+        args.keymap = Keymap::with_overrides(&flatten(self.keybinds), &flatten(self.normal_keybinds))
+            .map_err(|err| format!("invalid keybinds in the config file: {}", err))?;
+
+        if let Some(Command::List(list)) = &mut args.command {
+            list.columns = list.columns.take().or(self.columns);
+        }
+        Ok(())
+    }
+}
+
+fn flatten(keybinds: BTreeMap<String, Keys>) -> Vec<(String, Vec<String>)> {
+    keybinds
+        .into_iter()
+        .map(|(action, keys)| match keys {
+            Keys::One(key) => (action, vec![key]),
+            Keys::Many(keys) => (action, keys),
+        })
+        .collect()
+}
+
+fn parse_enum<T: ValueEnum>(key: &str, value: &str) -> Result<T, String> {
+    T::from_str(value, true).map_err(|_| {
+        let expected = T::value_variants()
+            .iter()
+            .filter_map(|variant| variant.to_possible_value())
+            .map(|possible| possible.get_name().to_string())
+            .collect::<Vec<String>>()
+            .join(", ");
+        format!("invalid value '{}' for '{}' in the config file, expected one of {}", value, key, expected)
+    })
+}
+
+fn parse_signal(value: &str) -> Result<Signal, String> {
+    value
+        .parse::<Signal>()
+        .map_err(|err| format!("invalid value for 'signal' in the config file: {}", err))
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::{CommandFactory, FromArgMatches};
+
+    use super::*;
+    use crate::interface::SortColumn;
+    use crate::matcher::SearchMode;
+
+    // Parses argv and applies a config file with contents on top, like main does
+    fn apply(contents: &str, argv: &[&str]) -> Result<Args, String> {
+        let matches = Args::command().get_matches_from(["fzk"].iter().chain(argv));
+        let mut args = Args::from_arg_matches(&matches).unwrap();
+        let config: Config = toml::from_str(contents).map_err(|err| err.to_string())?;
+        config.apply(&mut args, &matches)?;
+        Ok(args)
+    }
+
+    #[test]
+    fn file_fills_in_defaults() {
+        let args = apply("mode = \"regex\"\nsort = \"pid\"\nsignal = 15\nthreshold = 0.9\nreverse = true\n", &[]).unwrap();
+        assert_eq!(args.mode, SearchMode::Regex);
+        assert_eq!(args.sort, SortColumn::Pid);
+        assert_eq!(args.signal, Some(Signal::TERM));
+        assert_eq!(args.threshold, Some(0.9));
+        assert!(args.reverse);
+    }
+
+    #[test]
+    fn command_line_wins() {
+        let contents = "mode = \"regex\"\nsignal = \"TERM\"\nthreshold = 0.9\n";
+        let args = apply(contents, &["--mode", "exact", "-s", "HUP", "-t", "0.5"]).unwrap();
+        assert_eq!(args.mode, SearchMode::Exact);
+        assert_eq!(args.signal, Some(Signal::HUP));
+        assert_eq!(args.threshold, Some(0.5));
+    }
+
+    #[test]
+    fn no_flags_turn_off_file_booleans() {
+        let contents = "reverse = true\nvim = true\ncmdline = true\n";
+        let args = apply(contents, &["--no-reverse", "--no-vim"]).unwrap();
+        assert!(!args.reverse && !args.vim);
+        assert!(args.cmdline);
+
+        assert!(apply("", &["--reverse"]).unwrap().reverse);
+        assert!(!apply("", &["--reverse", "--no-reverse"]).unwrap().reverse);
+        assert!(apply("", &["--no-reverse", "--reverse"]).unwrap().reverse);
+    }
+
+    #[test]
+    fn invalid_entries() {
+        assert!(apply("mode = \"nope\"\n", &[]).unwrap_err().contains("mode"));
+        assert!(apply("signal = \"NOPE\"\n", &[]).is_err());
+        assert!(apply("colour = \"red\"\n", &[]).is_err());
+        assert!(apply("[keybinds]\nkill = \"k\"\n", &[]).unwrap_err().contains("keybinds"));
+        assert!(apply("[keybinds]\nkill = \"ctrl+d\"\nquit = \"ctrl+d\"\n", &[]).is_err());
+    }
+}
@@ -1,14 +1,36 @@
-use std::{cmp::Ordering, process::Command, u64};
-use rust_fuzzy_search::fuzzy_search_threshold;
+mod details;
+mod escalation;
+mod fixture;
+mod source;
+
+use std::{cmp::Ordering, collections::{HashMap, HashSet, VecDeque}, io, time::{Duration, Instant}};
+use clap::ValueEnum;
+
+use crate::{matcher::Pattern, query::Query, signal::Signal};
+
+pub use details::ProcessDetails;
+pub use escalation::{Escalation, EscalationState};
+pub use fixture::FixtureSource;
+pub use source::{ProcessSource, Sample, SystemSource};
 
 pub trait ProcessMonitor {
-    fn get_procs_from_system(&mut self) -> ();
-    fn kill_proc(&mut self, proc: &Process) -> ();
-    fn kill_proc_list(&mut self, name: &str) -> ();
-    fn get_procs_by_name_fuzzy(&self, search: &str, search_pid: bool) -> Option<Vec<Process>>;
+    fn get_procs_from_system(&mut self);
+    fn kill_proc(&mut self, proc: &Process, signal: Signal) -> io::Result<()>;
+    fn terminate_proc(&mut self, proc: &Process);
+    fn poll_escalations(&mut self);
+    fn kill_proc_list(&mut self, procs: &[Process], signal: Signal) -> Vec<(Process, io::Error)>;
+    fn get_procs_by_query(&self, query: &Query) -> Vec<Process>;
+    fn get_procs_by_pattern(&self, pattern: &Pattern, field: SearchField) -> Vec<Process>;
     fn get_all_procs(&self) -> Option<Vec<Process>>;
+    fn get_procs_by_ids(&self, ids: &HashSet<ProcessId>) -> Vec<Process>;
+    fn get_proc_tree(&self, root: &Process, order: TreeOrder) -> Vec<Process>;
+    fn get_proc_details(&mut self, proc: &Process) -> io::Result<ProcessDetails>;
 }
 
+// A pid paired with the process start time, unlike the pid alone this stays
+// unique for the whole life of the system
+pub type ProcessId = (u64, u64);
+
 // #[cfg(target_os = "windows")]
 // const KILL_COMMAND: &'static str = "taskkill /T";
 // #[cfg(any(target_os = "linux", target_os = "macos"))]
@@ -20,17 +42,128 @@ pub trait ProcessMonitor {
 // const UPDATE_COMMAND: &'static str = "ps -A --format comm,pid,%mem,%cpu";
 
 #[cfg(target_os = "windows")]
-pub const HEADERS: [&'static str; 3] = ["Command", "PID", "Memory Usage"];
+pub const HEADERS: [&str; 4] = ["Command", "PID", "Memory Usage", "Command Line"];
 #[cfg(any(target_os = "linux", target_os = "macos"))]
-pub const HEADERS: [&'static str; 4] = ["Command", "PID", "Memory Usage (%)", "CPU Usage (%)"];
+pub const HEADERS: [&str; 5] = ["Command", "PID", "Memory Usage (%)", "CPU Usage (%)", "Command Line"];
+
+// The columns the process list can be sorted by, in the same order as HEADERS
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum SortColumn {
+    Command,
+    Pid,
+    Mem,
+    #[cfg(any(target_os = "linux", target_os = "macos"))]
+    Cpu,
+    CommandLine,
+}
+
+impl SortColumn {
+    #[cfg(target_os = "windows")]
+    pub const ALL: [SortColumn; HEADERS.len()] = [Self::Command, Self::Pid, Self::Mem, Self::CommandLine];
+    #[cfg(any(target_os = "linux", target_os = "macos"))]
+    pub const ALL: [SortColumn; HEADERS.len()] = [Self::Command, Self::Pid, Self::Mem, Self::Cpu, Self::CommandLine];
+
+    pub fn index(self) -> usize {
+        Self::ALL
+            .iter()
+            .position(|&col| col == self)
+            .unwrap_or(0)
+    }
+
+    pub fn next(self) -> Self {
+        Self::ALL[(self.index() + 1) % Self::ALL.len()]
+    }
+
+    // How much of the list's width the column gets relative to the others
+    pub fn width(self) -> u16 {
+        match self {
+            Self::CommandLine => 3,
+            _ => 1,
+        }
+    }
+
+    // Orders by the column, ties are broken by command and then pid so the
+    // order stays the same between refreshes
+    pub fn order(self, a: &Process, b: &Process, descending: bool) -> Ordering {
+        let ord = self.compare(a, b);
+        let ord = if descending { ord.reverse() } else { ord };
+
+        ord.then(a.command.cmp(&b.command))
+            .then(a.pid.cmp(&b.pid))
+    }
+
+    // Numeric columns compare by value rather than by their text
+    pub fn compare(self, a: &Process, b: &Process) -> Ordering {
+        match self {
+            Self::Command => a.command.cmp(&b.command),
+            Self::Pid => a.pid.cmp(&b.pid),
+            #[cfg(target_os = "windows")]
+            Self::Mem => a.get_mem_kib().cmp(&b.get_mem_kib()),
+            #[cfg(any(target_os = "linux", target_os = "macos"))]
+            Self::Mem => a.mem.total_cmp(&b.mem),
+            #[cfg(any(target_os = "linux", target_os = "macos"))]
+            Self::Cpu => a.cpu.total_cmp(&b.cpu),
+            Self::CommandLine => a.get_full_command().cmp(b.get_full_command()),
+        }
+    }
+}
+
+// What part of a process the search box is matched against
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SearchField {
+    Command,
+    Pid,
+    CommandLine,
+}
+
+impl SearchField {
+    // Searches starting with a digit look up pids
+    pub fn detect(search: &str, cmdline: bool) -> Self {
+        if cmdline {
+            Self::CommandLine
+        } else if search.starts_with(|c: char| c.is_ascii_digit()) {
+            Self::Pid
+        } else {
+            Self::Command
+        }
+    }
+
+    // The part of proc that gets matched
+    pub fn text(self, proc: &Process) -> String {
+        match self {
+            Self::Command => proc.get_command().replace(".exe", ""),
+            Self::Pid => proc.get_pid().to_string(),
+            Self::CommandLine => proc.get_full_command().to_string(),
+        }
+    }
+}
 
 #[derive(Clone)]
 pub struct Process {
     command: String,
     pid: u64,
+    // 0 for processes without a parent or if the source can't tell
+    ppid: u64,
+    // Ticks after boot that the process started, 0 if the source can't tell.
+    // Together with the pid this identifies a process even if the pid is reused
+    start_time: u64,
+    // Name of the owner, or the uid if it has no name. Empty if unknown
+    user: String,
+    // Every argument joined with spaces, and the path of the executable.
+    // Either is empty if the source can't tell
+    cmdline: String,
+    exe: String,
+    // Single letter state like ps shows it, e.g. R, S or Z. '?' if unknown
+    state: char,
+    #[cfg(target_os = "windows")]
     mem: String,
     #[cfg(any(target_os = "linux", target_os = "macos"))]
-    cpu: String
+    mem: f32,
+    #[cfg(any(target_os = "linux", target_os = "macos"))]
+    cpu: f32,
+    // Total user and system ticks, used to compute cpu usage between samples
+    #[cfg(any(target_os = "linux", target_os = "macos"))]
+    cpu_time: u64
 }
 
 impl Process {
@@ -38,9 +171,20 @@ impl Process {
         Self {
             command: String::new(),
             pid: u64::MAX,
+            ppid: 0,
+            start_time: 0,
+            user: String::new(),
+            cmdline: String::new(),
+            exe: String::new(),
+            state: '?',
+            #[cfg(target_os = "windows")]
             mem: String::new(),
-             #[cfg(any(target_os = "linux", target_os = "macos"))]
-            cpu: String::new()
+            #[cfg(any(target_os = "linux", target_os = "macos"))]
+            mem: 0.0,
+            #[cfg(any(target_os = "linux", target_os = "macos"))]
+            cpu: 0.0,
+            #[cfg(any(target_os = "linux", target_os = "macos"))]
+            cpu_time: 0
         }
     }
 
@@ -52,49 +196,250 @@ impl Process {
         self.pid
     }
 
+    pub fn get_ppid(&self) -> u64 {
+        self.ppid
+    }
+
+    pub fn get_user(&self) -> &str {
+        &self.user
+    }
+
+    pub fn get_state(&self) -> char {
+        self.state
+    }
+
+    // The command line, falling back to the executable and then the command
+    // name for processes without one, like kernel threads
+    pub fn get_full_command(&self) -> &str {
+        if !self.cmdline.is_empty() {
+            &self.cmdline
+        } else if !self.exe.is_empty() {
+            &self.exe
+        } else {
+            &self.command
+        }
+    }
+
+    // A field by the name used in output formats, None for unknown names
+    pub fn get_field(&self, name: &str) -> Option<String> {
+        let value = match name {
+            "pid" => self.pid.to_string(),
+            "ppid" => self.ppid.to_string(),
+            "user" => self.user.clone(),
+            "command" => self.command.clone(),
+            "cmdline" => self.get_full_command().to_string(),
+            "exe" => self.exe.clone(),
+            "state" => self.state.to_string(),
+            #[cfg(target_os = "windows")]
+            "mem" => self.mem.clone(),
+            #[cfg(any(target_os = "linux", target_os = "macos"))]
+            "mem" => format!("{:.1}", self.mem),
+            #[cfg(any(target_os = "linux", target_os = "macos"))]
+            "cpu" => format!("{:.1}", self.cpu),
+            _ => return None,
+        };
+        Some(value)
+    }
+
+    // Fills in every {field} in template, e.g. "{pid} {command}". Unknown
+    // fields are left as they are
+    pub fn format(&self, template: &str) -> String {
+        let mut out = String::new();
+        let mut rest = template;
+
+        while let Some(open) = rest.find('{') {
+            out.push_str(&rest[..open]);
+            let after = &rest[open..];
+
+            match after.find('}').and_then(|close| Some((close, self.get_field(&after[1..close])?))) {
+                Some((close, value)) => {
+                    out.push_str(&value);
+                    rest = &after[close + 1..];
+                },
+                None => {
+                    out.push('{');
+                    rest = &after[1..];
+                },
+            }
+        }
+        out.push_str(rest);
+        out
+    }
+
+    pub fn get_id(&self) -> ProcessId {
+        (self.pid, self.start_time)
+    }
+
+    pub fn is_same_process(&self, other: &Process) -> bool {
+        self.pid == other.pid && self.start_time == other.start_time
+    }
+
+    #[cfg(target_os = "windows")]
     pub fn get_mem(&self) -> &str {
         &self.mem
     }
 
+    // tasklist reports memory like "12,345 KiB"
+    #[cfg(target_os = "windows")]
+    pub fn get_mem_kib(&self) -> u64 {
+        self.mem
+            .chars()
+            .filter(|c| c.is_ascii_digit())
+            .collect::<String>()
+            .parse::<u64>()
+            .unwrap_or(0)
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "macos"))]
+    pub fn get_mem(&self) -> f32 {
+        self.mem
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "macos"))]
+    pub fn get_cpu(&self) -> f32 {
+        self.cpu
+    }
+
+    // Usage rounded to the one decimal the list shows, so filters and json
+    // output agree with the screen. Widening the f32 as is would turn 0.3
+    // into 0.30000001192092896
+    #[cfg(any(target_os = "linux", target_os = "macos"))]
+    pub fn get_displayed_mem(&self) -> f64 {
+        displayed(self.mem)
+    }
+
     #[cfg(any(target_os = "linux", target_os = "macos"))]
-    pub fn get_cpu(&self) -> &str {
-        &self.cpu
+    pub fn get_displayed_cpu(&self) -> f64 {
+        displayed(self.cpu)
     }
 }
 
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+fn displayed(value: f32) -> f64 {
+    format!("{:.1}", value)
+        .parse::<f64>()
+        .unwrap_or(f64::NAN)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum CpuMode {
+    // 100% means one fully busy core, like top
+    PerCore,
+    // 100% means every core is fully busy
+    Normalized,
+}
+
+// The order processes in a tree are signaled in
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum TreeOrder {
+    // Children before their parents, so nothing gets reparented to init
+    // and keeps running while the tree is being killed
+    BottomUp,
+    // Parents before their children, so nothing can spawn new children
+    TopDown,
+}
+
 pub struct Monitor {
     interval: f32,
-    threshold: f32,
     num_matches: usize,
-    current_procs: Vec<Process>
+    current_procs: Vec<Process>,
+    source: Box<dyn ProcessSource>,
+    #[cfg_attr(target_os = "windows", allow(dead_code))]
+    cpu_mode: CpuMode,
+    #[cfg(any(target_os = "linux", target_os = "macos"))]
+    prev_cpu_times: HashMap<(u64, u64), u64>,
+    #[cfg(any(target_os = "linux", target_os = "macos"))]
+    prev_total_time: Option<u64>,
+    grace_period: Duration,
+    escalations: Vec<Escalation>,
+    last_escalation_poll: Option<Instant>
 }
 
+// How often escalations check whether their process has exited yet
+const ESCALATION_POLL_INTERVAL: Duration = Duration::from_millis(250);
+
 impl Monitor {
-    pub fn new(inter: f32, thres: f32, num: usize) -> Self {
+    pub fn new(inter: f32, num: usize, cpu_mode: CpuMode, grace: f32, source: Box<dyn ProcessSource>) -> Self {
         Self {
             interval: inter.max(1.0),
-            threshold: thres.max(0.0).min(1.0),
             num_matches: num.max(1),
             current_procs: Vec::new(),
+            source,
+            cpu_mode,
+            #[cfg(any(target_os = "linux", target_os = "macos"))]
+            prev_cpu_times: HashMap::new(),
+            #[cfg(any(target_os = "linux", target_os = "macos"))]
+            prev_total_time: None,
+            grace_period: Duration::from_secs_f32(grace.max(0.0)),
+            escalations: Vec::new(),
+            last_escalation_poll: None,
         }
     }
 
-    pub fn get_interval(&self) -> f32 {
-        self.interval
+    pub fn get_escalations(&self) -> &[Escalation] {
+        &self.escalations
     }
 
-    #[cfg(debug_assertions)]
-    pub fn print_all_procs(&self) -> () {
-        self.current_procs.iter()
-                .for_each(|proc| {
-                    println!("{} {}", proc.command, proc.pid);
-                });
+    fn remove_proc(&mut self, proc: &Process) {
+        if let Some(spot) = self.current_procs
+            .iter()
+            .position(|p| p.is_same_process(proc)) {
+            self.current_procs.remove(spot);
+        }
+    }
+
+    // Replace the cpu usage reported by the source with the usage over the
+    // last interval, using the ticks recorded from the previous sample
+    #[cfg(any(target_os = "linux", target_os = "macos"))]
+    fn update_cpu_usage(&mut self, sample: &mut Sample) {
+        let Some(total_time) = sample.total_time else {
+            return;
+        };
+
+        if self.prev_total_time.is_none() && self.cpu_mode == CpuMode::Normalized {
+            // The fallback from the source is per core
+            sample.procs
+                .iter_mut()
+                .for_each(|proc| proc.cpu /= sample.num_cpus as f32);
+        }
+
+        if let Some(prev_total_time) = self.prev_total_time {
+            let elapsed = total_time.saturating_sub(prev_total_time);
+
+            if elapsed > 0 {
+                let scale = match self.cpu_mode {
+                    CpuMode::PerCore => sample.num_cpus as f64,
+                    CpuMode::Normalized => 1.0,
+                };
+
+                sample.procs
+                    .iter_mut()
+                    .for_each(|proc| {
+                        let used = match self.prev_cpu_times.get(&(proc.pid, proc.start_time)) {
+                            Some(&prev) => proc.cpu_time.saturating_sub(prev),
+                            // Started since the last sample, so all of its time is new
+                            None => proc.cpu_time,
+                        };
+                        proc.cpu = (used as f64 / elapsed as f64 * scale * 100.0) as f32;
+                    });
+            }
+        }
+
+        self.prev_total_time = Some(total_time);
+        self.prev_cpu_times = sample.procs
+            .iter()
+            .map(|proc| ((proc.pid, proc.start_time), proc.cpu_time))
+            .collect();
+    }
+
+    pub fn get_interval(&self) -> f32 {
+        self.interval
     }
 }
 
 impl ProcessMonitor for Monitor {
     fn get_all_procs(&self) -> Option<Vec<Process>> {
-        if self.current_procs.len() == 0 {
+        if self.current_procs.is_empty() {
             None
         } else {
             Some(
@@ -103,203 +448,347 @@ impl ProcessMonitor for Monitor {
         }
     }
 
-    fn get_procs_by_name_fuzzy(&self, search: &str, search_pid: bool) -> Option<Vec<Process>> {
-        let procs =
-            self.current_procs
-            .iter()
-            .map(|proc| {
-                if search_pid {
-                    proc.get_pid().to_string()
-                } else {
-                    proc.get_command().replace(".exe", "")
-                }
-            })
-            .collect::<Vec<String>>();
-        let refs = procs
+    fn get_procs_by_ids(&self, ids: &HashSet<ProcessId>) -> Vec<Process> {
+        self.current_procs
             .iter()
-            .map(String::as_str)
-            .collect::<Vec<&str>>();
+            .filter(|proc| ids.contains(&proc.get_id()))
+            .cloned()
+            .collect()
+    }
 
-        let mut matches = fuzzy_search_threshold(search, &refs, self.threshold);
-        matches
-            .sort_by(|(_, score1), (_, score2)| {
-                if score1 > score2 {
-                    Ordering::Less
-                } else {
-                    Ordering::Greater
-                }
-            });
-        let matches = 
-            matches
+    fn get_proc_tree(&self, root: &Process, order: TreeOrder) -> Vec<Process> {
+        let mut children: HashMap<u64, Vec<&Process>> = HashMap::new();
+        self.current_procs
             .iter()
-            .map(|&(key, _)| key)
-            .take(self.num_matches)
-            .collect::<Vec<&str>>();
-
-        if matches.len() == 0 {
-            None
-        } else {
-            let mut ret: Vec<Process> = Vec::new();
-            matches
-                .iter()
-                .for_each(|&p| {
-                    if let Some(spot) = self.current_procs
-                        .iter()
-                        .position(|proc| {
-                            if search_pid {
-                                proc.get_pid().to_string() == p
-                            } else {
-                                proc.get_command().replace(".exe", "") == p
-                            }
-                        }) {
-                            ret.push(self.current_procs[spot].clone());
-                        }
-                });
+            .filter(|proc| proc.pid != proc.ppid)
+            .for_each(|proc| children.entry(proc.ppid).or_default().push(proc));
+
+        // Breadth first, so every process comes after its parent. Anything
+        // already seen is skipped in case a reused pid made a loop
+        let mut tree = vec![root.clone()];
+        let mut seen = HashSet::from([root.pid]);
+        let mut queue = VecDeque::from([root.pid]);
+        while let Some(pid) = queue.pop_front() {
+            if let Some(kids) = children.get(&pid) {
+                kids.iter()
+                    .filter(|kid| seen.insert(kid.pid))
+                    .for_each(|&kid| {
+                        tree.push(kid.clone());
+                        queue.push_back(kid.pid);
+                    });
+            }
+        }
 
-            Some(
-                ret
-            )
+        if order == TreeOrder::BottomUp {
+            tree.reverse();
         }
+        tree
     }
 
-    #[cfg(target_os = "windows")]
-    fn get_procs_from_system(&mut self) -> () {
-        // Get the current list of processes
-        let output = Command::new("tasklist")
-            .args("/NH /FO TABLE".split(" "))
-            .output()
-            .expect("Failed to exec tasklist");
-
-        // Check to see if the command executed successfully
-        if !output.status.success() {
-            return;
+    fn get_proc_details(&mut self, proc: &Process) -> io::Result<ProcessDetails> {
+        let mut details = self.source.details(proc)?;
+
+        // Walk up through the last sample, stopping at anything already seen
+        // in case a reused pid made a loop
+        let by_pid = self.current_procs
+            .iter()
+            .map(|p| (p.pid, p))
+            .collect::<HashMap<u64, &Process>>();
+        let mut seen = HashSet::from([proc.pid]);
+        let mut ppid = proc.ppid;
+        while let Some(parent) = by_pid.get(&ppid).filter(|_| seen.insert(ppid)) {
+            details.parents.push((parent.pid, parent.command.clone()));
+            ppid = parent.ppid;
         }
-        let Ok(res) = String::from_utf8(output.stdout) else {
-            return;
-        };
 
-        // Clean out the old processes since we have a new list
-        self.current_procs.clear();
-
-        res.lines().for_each(|line| {
-            // Iterate over every task and insert the process into the vector attached to that command (includes children)
-            let mut p: Process = Process::new();
-            let mut units: &str = "";
-
-            // The columns are gotten from TABLE format in tasklist
-            line.split_ascii_whitespace()
-                .enumerate()
-                .for_each(|(i, col)| {
-                    match i {
-                        0 => p.command = col.to_string(),
-                        1 => p.pid = col.parse::<u64>().unwrap_or(u64::MAX),
-                        4 => p.mem = col.to_string(),
-                        5 => units = col,
-                        _ => (),
-                    }
-                });
-
-            if p.pid != u64::MAX {
-                // Add the bytes units to the number
-                p.mem.push_str(" ");
-                p.mem.push_str(units);
-                p.mem.push_str("iB");
-                self.current_procs.push(p);
-            }
-        });
+        Ok(details)
     }
 
-    #[cfg(any(target_os = "linux", target_os = "macos"))]
-    fn get_procs_from_system(&mut self) -> () {
-        // Get the current list of processes
-        let output = Command::new("ps")
-            .args("-A --format comm,pid,%mem,%cpu".split(" "))
-            .output()
-            .expect("Failed to exec ps");
-
-        // Check to see if the command executed successfully
-        if !output.status.success() {
-            return;
-        }
-        let Ok(res) = String::from_utf8(output.stdout) else {
-            return;
-        };
+    fn get_procs_by_query(&self, query: &Query) -> Vec<Process> {
+        let filtered = self.current_procs
+            .iter()
+            .filter(|proc| query.matches(proc));
 
-        self.current_procs.clear();
+        // With only filters there is nothing to rank by
+        if query.get_text().is_empty() {
+            return filtered.cloned().collect();
+        }
 
-        res.lines().skip(1).for_each(|line| {
-            let mut p: Process = Process::new();
-            let mut comm: String = String::new();
+        // Score every process on its own so processes sharing a name each
+        // keep their own entry instead of collapsing onto the first one
+        let mut matches = filtered
+            .filter_map(|proc| {
+                query.score(proc)
+                    .map(|score| (proc, score))
+            })
+            .collect::<Vec<(&Process, f32)>>();
 
-            line.split_ascii_whitespace().enumerate().for_each(|(i, col)| {
-                match i {
-                    0 => p.command = col.to_string(),
-                    1 => p.pid = col.parse::<u64>().unwrap_or(u64::MAX),
-                    2 => p.mem = col.to_string(),
-                    3 => p.cpu = col.to_string(),
-                    _ => (),
-                }
+        // Ties are broken by command and then pid so the order doesn't
+        // shuffle around between refreshes
+        matches
+            .sort_by(|&(first, score1), &(second, score2)| {
+                score2
+                    .partial_cmp(&score1)
+                    .unwrap_or(Ordering::Equal)
+                    .then_with(|| first.get_command().cmp(second.get_command()))
+                    .then_with(|| first.get_pid().cmp(&second.get_pid()))
             });
 
-            self.current_procs.push(p);
-        });
+        matches
+            .iter()
+            .take(self.num_matches)
+            .map(|&(proc, _)| proc.clone())
+            .collect()
     }
 
-    fn kill_proc_list(&mut self, name: &str) -> () {
-        let proc_list = self.current_procs
+    // Every process that pattern matches, unlike a query this isn't limited
+    // to the best matches
+    fn get_procs_by_pattern(&self, pattern: &Pattern, field: SearchField) -> Vec<Process> {
+        self.current_procs
             .iter()
-            .filter(|proc| proc.get_command() == name)
-            .map(|proc| proc.clone())
-            .collect::<Vec<Process>>();
-        proc_list
-            .iter()
-            .for_each(|p| self.kill_proc(&p));
+            .filter(|proc| pattern.find(&field.text(proc)).is_some())
+            .cloned()
+            .collect()
     }
 
-    #[cfg(target_os = "windows")]
-    fn kill_proc(&mut self, proc: &Process) -> () {
-        let res = Command::new("taskkill")
-            .arg("/T")
-            .arg("/F")
-            .arg("/PID")
-            .arg(proc.pid.to_string())
-            .output();
-
-        let Ok(output) = res else {
+    fn get_procs_from_system(&mut self) {
+        // Keep showing the last list if the source failed this time around
+        let Ok(mut sample) = self.source.enumerate() else {
             return;
         };
 
-        if !output.status.success() {
-            return;
-        }
+        #[cfg(any(target_os = "linux", target_os = "macos"))]
+        self.update_cpu_usage(&mut sample);
 
-        if let Some(spot) = self.current_procs
+        self.current_procs = sample.procs;
+    }
+
+    // Signals exactly the processes given, never anything found by searching
+    // again, so nothing gets signaled that wasn't shown first. Returns the
+    // processes that couldn't be signaled, which stay in the list to retry
+    fn kill_proc_list(&mut self, procs: &[Process], signal: Signal) -> Vec<(Process, io::Error)> {
+        procs
             .iter()
-            .position(|p| p.get_pid() == proc.get_pid()) {
-            self.current_procs.remove(spot);
-        }
+            .filter_map(|p| {
+                self.kill_proc(p, signal)
+                    .err()
+                    .map(|err| (p.clone(), err))
+            })
+            .collect()
     }
 
-    #[cfg(any(target_os = "linux", target_os = "macos"))]
-    fn kill_proc(&mut self, proc: &Process) -> () {
-        let res = Command::new("kill")
-            .arg("-9")
-            .arg(proc.pid.to_string())
-            .output();
+    fn kill_proc(&mut self, proc: &Process, signal: Signal) -> io::Result<()> {
+        self.source.signal(proc, signal)?;
+
+        // Only drop the process from the list right away if it can't survive
+        // the signal, otherwise wait for the next refresh to see what happened
+        if signal.always_ends_process() {
+            self.remove_proc(proc);
+        }
+        Ok(())
+    }
 
-        let Ok(output) = res else {
+    fn terminate_proc(&mut self, proc: &Process) {
+        // Already on its way out
+        if self.escalations
+            .iter()
+            .any(|e| e.get_proc().is_same_process(proc)
+                && matches!(e.get_state(), EscalationState::Terminating(_))) {
             return;
+        }
+
+        let state = match self.source.signal(proc, Signal::TERM) {
+            Ok(()) => EscalationState::Terminating(Instant::now() + self.grace_period),
+            Err(err) => EscalationState::Failed(err.to_string()),
         };
 
-        if !output.status.success() {
-            eprintln!("Not successful, pid = {}", proc.pid.to_string());
+        self.escalations.push(Escalation::new(proc.clone(), state));
+    }
+
+    // Checks on every process that was sent SIGTERM without waiting on any of
+    // them, so this is cheap enough to call on every frame
+    fn poll_escalations(&mut self) {
+        let now = Instant::now();
+        // Called every frame while holding the lock, and checking on a process
+        // can mean running ps or tasklist, so only look every so often
+        if self.last_escalation_poll.is_some_and(|last| now.duration_since(last) < ESCALATION_POLL_INTERVAL) {
             return;
         }
+        self.last_escalation_poll = Some(now);
+        let mut ended = Vec::new();
+
+        for escalation in self.escalations.iter_mut() {
+            let EscalationState::Terminating(deadline) = *escalation.get_state() else {
+                continue;
+            };
+
+            let proc = escalation.get_proc().clone();
+            if !self.source.is_alive(&proc) {
+                escalation.set_state(EscalationState::ExitedCleanly);
+                ended.push(proc);
+            } else if now >= deadline {
+                match self.source.signal(&proc, Signal::KILL) {
+                    Ok(()) => {
+                        escalation.set_state(EscalationState::Killed);
+                        ended.push(proc);
+                    },
+                    Err(err) => escalation.set_state(EscalationState::Failed(err.to_string())),
+                }
+            }
+        }
 
-        if let Some(spot) = self.current_procs
+        self.escalations.retain(|e| !e.is_expired(now));
+        ended
             .iter()
-            .position(|p| p.get_pid() == proc.get_pid()) {
-            self.current_procs.remove(spot);
+            .for_each(|proc| self.remove_proc(proc));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::matcher::MatchOptions;
+
+    fn monitor() -> Monitor {
+        let source = FixtureSource::parse(include_str!("../fixtures/sample.tsv")).unwrap();
+        let mut monitor = Monitor::new(3.0, 25, CpuMode::PerCore, 5.0, Box::new(source));
+        monitor.get_procs_from_system();
+        monitor
+    }
+
+    // Hands out the samples it was given one at a time
+    #[cfg(any(target_os = "linux", target_os = "macos"))]
+    struct Samples(VecDeque<Sample>);
+
+    #[cfg(any(target_os = "linux", target_os = "macos"))]
+    impl ProcessSource for Samples {
+        fn enumerate(&mut self) -> io::Result<Sample> {
+            self.0.pop_front().ok_or_else(|| io::Error::other("out of samples"))
+        }
+
+        fn signal(&mut self, _proc: &Process, _signal: Signal) -> io::Result<()> {
+            Ok(())
         }
     }
+
+    #[cfg(any(target_os = "linux", target_os = "macos"))]
+    fn cpu_process(pid: u64, start_time: u64, cpu_time: u64, cpu: f32) -> Process {
+        let mut proc = Process::new();
+        proc.pid = pid;
+        proc.start_time = start_time;
+        proc.cpu_time = cpu_time;
+        proc.cpu = cpu;
+        proc
+    }
+
+    // Two samples 100 ticks apart on each of 4 cpus. Between them pid 1 is
+    // reused by a new process and pid 2 uses 80 ticks
+    #[cfg(any(target_os = "linux", target_os = "macos"))]
+    fn cpu_usage(mode: CpuMode) -> Vec<Vec<f32>> {
+        let samples = [
+            (1000, vec![cpu_process(1, 10, 100, 50.0), cpu_process(2, 10, 0, 0.0)]),
+            (1400, vec![cpu_process(1, 20, 20, 0.0), cpu_process(2, 10, 80, 0.0)]),
+        ];
+        let samples = samples
+            .into_iter()
+            .map(|(total_time, procs)| Sample { procs, total_time: Some(total_time), num_cpus: 4 })
+            .collect();
+
+        let mut monitor = Monitor::new(3.0, 25, mode, 5.0, Box::new(Samples(samples)));
+        (0..2)
+            .map(|_| {
+                monitor.get_procs_from_system();
+                monitor.get_all_procs().unwrap().iter().map(Process::get_cpu).collect()
+            })
+            .collect()
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "macos"))]
+    #[test]
+    fn cpu_usage_per_core() {
+        // The first sample has nothing to compare against, so the source's
+        // own per core figure is kept
+        assert_eq!(cpu_usage(CpuMode::PerCore), [[50.0, 0.0], [20.0, 80.0]]);
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "macos"))]
+    #[test]
+    fn cpu_usage_normalized() {
+        assert_eq!(cpu_usage(CpuMode::Normalized), [[12.5, 0.0], [5.0, 20.0]]);
+    }
+
+    fn pids(procs: &[Process]) -> Vec<u64> {
+        procs.iter().map(Process::get_pid).collect()
+    }
+
+    #[test]
+    fn same_name_keeps_every_process() {
+        let query = Query::parse("bash", &MatchOptions::default(), false).unwrap();
+        let found = monitor().get_procs_by_query(&query);
+        assert_eq!(pids(&found[..3]), [1200, 1201, 1350]);
+    }
+
+    #[test]
+    fn kill_proc_list_signals_only_what_it_is_given() {
+        let mut monitor = monitor();
+        let pattern = Pattern::exact("nginx");
+        let mut procs = monitor.get_procs_by_pattern(&pattern, SearchField::Command);
+        assert_eq!(pids(&procs), [2000, 2001]);
+
+        let mut gone = Process::new();
+        gone.pid = 9999;
+        procs.push(gone);
+
+        let failures = monitor.kill_proc_list(&procs, Signal::KILL);
+        assert_eq!(failures.len(), 1);
+        assert_eq!(failures[0].0.get_pid(), 9999);
+        assert_eq!(failures[0].1.kind(), io::ErrorKind::NotFound);
+
+        monitor.get_procs_from_system();
+        let left = pids(&monitor.get_all_procs().unwrap());
+        assert!(!left.contains(&2000) && !left.contains(&2001));
+        assert!(left.contains(&4200));
+    }
+
+    #[test]
+    fn kill_by_name_leaves_similar_names() {
+        let monitor = monitor();
+        let fuzzy = Query::parse("node", &MatchOptions::default(), false).unwrap();
+        assert_eq!(pids(&monitor.get_procs_by_query(&fuzzy)[..2]), [4200, 4201]);
+
+        let name = SearchField::Command.text(&monitor.get_procs_by_query(&fuzzy)[0]);
+        let found = monitor.get_procs_by_pattern(&Pattern::exact(&name), SearchField::Command);
+        assert_eq!(pids(&found), [4200]);
+        assert!(monitor.get_procs_by_pattern(&Pattern::exact("Node"), SearchField::Command).is_empty());
+    }
+
+    #[test]
+    fn proc_tree_order() {
+        let monitor = monitor();
+        let bash = &monitor.get_procs_by_pattern(&Pattern::exact("-bash"), SearchField::CommandLine)[0];
+
+        let top_down = monitor.get_proc_tree(bash, TreeOrder::TopDown);
+        assert_eq!(pids(&top_down), [1200, 1201, 1350, 5000, 4200, 4201]);
+
+        let bottom_up = monitor.get_proc_tree(bash, TreeOrder::BottomUp);
+        assert_eq!(pids(&bottom_up), [4201, 4200, 5000, 1350, 1201, 1200]);
+    }
+
+    #[test]
+    fn proc_tree_survives_a_ppid_loop() {
+        let source = FixtureSource::parse("command\tpid\tppid\na\t10\t11\nb\t11\t10\n").unwrap();
+        let mut monitor = Monitor::new(3.0, 25, CpuMode::PerCore, 5.0, Box::new(source));
+        monitor.get_procs_from_system();
+
+        let root = &monitor.get_all_procs().unwrap()[0];
+        assert_eq!(pids(&monitor.get_proc_tree(root, TreeOrder::TopDown)), [10, 11]);
+    }
+
+    #[test]
+    fn stopped_processes_stay_listed() {
+        let mut monitor = monitor();
+        let node = monitor.get_procs_by_pattern(&Pattern::exact("node"), SearchField::Command);
+        assert!(monitor.kill_proc_list(&node, Signal::STOP).is_empty());
+        assert!(pids(&monitor.get_all_procs().unwrap()).contains(&4200));
+    }
 }
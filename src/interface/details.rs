@@ -0,0 +1,75 @@
+use std::time::Duration;
+
+use super::Process;
+
+// Everything the detail pane shows about a single process. Sources fill in
+// what they can, None is something they can't tell or weren't allowed to read
+#[derive(Clone)]
+pub struct ProcessDetails {
+    pub pid: u64,
+    pub command: String,
+    pub cmdline: String,
+    pub exe: Option<String>,
+    pub cwd: Option<String>,
+    pub user: String,
+    pub group: Option<String>,
+    // From the direct parent up to the root, as pid and command
+    pub parents: Vec<(u64, String)>,
+    // Local time the process started, and how long ago that was
+    pub start_time: Option<String>,
+    pub running_for: Option<Duration>,
+    pub state: char,
+    pub threads: Option<u64>,
+    pub nice: Option<i64>,
+    pub priority: Option<i64>,
+    // Both in bytes
+    pub rss: Option<u64>,
+    pub vsz: Option<u64>,
+    pub open_files: Option<usize>,
+    pub cgroups: Option<Vec<String>>,
+    // NAME=value entries in the order the process has them
+    pub environ: Option<Vec<String>>,
+}
+
+impl ProcessDetails {
+    // Only what the process list already knows, for sources that can't
+    // look any closer
+    pub fn from_process(proc: &Process) -> Self {
+        Self {
+            pid: proc.pid,
+            command: proc.command.clone(),
+            cmdline: proc.get_full_command().to_string(),
+            exe: (!proc.exe.is_empty()).then(|| proc.exe.clone()),
+            cwd: None,
+            user: proc.user.clone(),
+            group: None,
+            parents: Vec::new(),
+            start_time: None,
+            running_for: None,
+            state: proc.state,
+            threads: None,
+            nice: None,
+            priority: None,
+            rss: None,
+            vsz: None,
+            open_files: None,
+            cgroups: None,
+            environ: None,
+        }
+    }
+
+    // The state letter spelled out, like ps(1) describes them
+    pub fn state_name(&self) -> &'static str {
+        match self.state {
+            'R' => "running",
+            'S' => "sleeping",
+            'D' => "uninterruptible sleep",
+            'I' => "idle",
+            'T' => "stopped",
+            't' => "stopped by debugger",
+            'Z' => "zombie",
+            'X' => "dead",
+            _ => "unknown",
+        }
+    }
+}
@@ -0,0 +1,66 @@
+use std::time::{Duration, Instant};
+
+use super::Process;
+
+// How long a finished escalation stays on screen before it is dropped
+const FINISHED_DISPLAY_TIME: Duration = Duration::from_secs(5);
+
+#[derive(Clone)]
+pub enum EscalationState {
+    // SIGTERM was sent, SIGKILL follows at the deadline if it is still alive
+    Terminating(Instant),
+    ExitedCleanly,
+    Killed,
+    Failed(String),
+}
+
+// A process being ended with SIGTERM first and SIGKILL only if it doesn't
+// exit within the grace period
+#[derive(Clone)]
+pub struct Escalation {
+    proc: Process,
+    state: EscalationState,
+    updated: Instant,
+}
+
+impl Escalation {
+    pub fn new(proc: Process, state: EscalationState) -> Self {
+        Self {
+            proc,
+            state,
+            updated: Instant::now(),
+        }
+    }
+
+    pub fn get_proc(&self) -> &Process {
+        &self.proc
+    }
+
+    pub fn get_state(&self) -> &EscalationState {
+        &self.state
+    }
+
+    pub fn set_state(&mut self, state: EscalationState) {
+        self.state = state;
+        self.updated = Instant::now();
+    }
+
+    pub fn is_expired(&self, now: Instant) -> bool {
+        !matches!(self.state, EscalationState::Terminating(_))
+            && now.duration_since(self.updated) >= FINISHED_DISPLAY_TIME
+    }
+
+    pub fn status_text(&self, now: Instant) -> String {
+        let status = match &self.state {
+            EscalationState::Terminating(deadline) => format!(
+                "terminating\u{2026} {}s left",
+                deadline.saturating_duration_since(now).as_secs_f32().ceil() as u64
+            ),
+            EscalationState::ExitedCleanly => String::from("exited cleanly"),
+            EscalationState::Killed => String::from("killed"),
+            EscalationState::Failed(err) => format!("failed: {}", err),
+        };
+
+        format!("{} ({}): {}", self.proc.get_command(), self.proc.get_pid(), status)
+    }
+}
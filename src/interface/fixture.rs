@@ -0,0 +1,116 @@
+use std::{fs, io, path::Path};
+
+use super::{source::{ProcessSource, Sample}, Process};
+use crate::signal::Signal;
+
+// An in-memory process source loaded from a fixture file, so the monitor and
+// the TUI can be exercised without touching real processes.
+//
+// The file is tab separated. The first non-comment line names the columns,
+// every following line is one process. Lines starting with '#' are ignored.
+//
+//     command	pid	mem	cpu
+//     bash	1200	0.1	0.0
+//
+// Signaling a fixture process removes it from the list, as if it had died,
+// except for SIGSTOP and SIGCONT which leave it in place. Processes owned by
+// root refuse every signal, like they would for an ordinary user.
+pub struct FixtureSource {
+    procs: Vec<Process>,
+}
+
+impl FixtureSource {
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let path = path.as_ref();
+        let contents = fs::read_to_string(path).map_err(|err| {
+            io::Error::new(err.kind(), format!("{}: {}", path.display(), err))
+        })?;
+
+        Self::parse(&contents).map_err(|msg| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{}: {}", path.display(), msg)
+            )
+        })
+    }
+
+    pub fn parse(contents: &str) -> Result<Self, String> {
+        let mut lines = contents
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty() && !line.starts_with('#'));
+
+        let Some((_, header)) = lines.next() else {
+            return Err(String::from("missing header line"));
+        };
+        let columns = header.split('\t').map(str::trim).collect::<Vec<&str>>();
+
+        if !columns.contains(&"pid") {
+            return Err(String::from("header must contain a pid column"));
+        }
+
+        let mut procs = Vec::new();
+        for (line_num, line) in lines {
+            let mut p = Process::new();
+
+            for (column, value) in columns.iter().zip(line.split('\t')) {
+                Self::set_field(&mut p, column, value.trim())
+                    .map_err(|msg| format!("line {}: {}", line_num + 1, msg))?;
+            }
+
+            procs.push(p);
+        }
+
+        Ok(Self { procs })
+    }
+
+    fn set_field(p: &mut Process, column: &str, value: &str) -> Result<(), String> {
+        match column {
+            "command" => p.command = value.to_string(),
+            "user" => p.user = value.to_string(),
+            "cmdline" => p.cmdline = value.to_string(),
+            "exe" => p.exe = value.to_string(),
+            "state" => p.state = Self::parse_value(column, value)?,
+            "pid" => p.pid = Self::parse_value(column, value)?,
+            "ppid" => p.ppid = Self::parse_value(column, value)?,
+            "start_time" => p.start_time = Self::parse_value(column, value)?,
+            #[cfg(target_os = "windows")]
+            "mem" => p.mem = value.to_string(),
+            #[cfg(any(target_os = "linux", target_os = "macos"))]
+            "mem" => p.mem = Self::parse_value(column, value)?,
+            #[cfg(any(target_os = "linux", target_os = "macos"))]
+            "cpu" => p.cpu = Self::parse_value(column, value)?,
+            _ => return Err(format!("unknown column '{}'", column)),
+        }
+        Ok(())
+    }
+
+    fn parse_value<T: std::str::FromStr>(column: &str, value: &str) -> Result<T, String> {
+        value
+            .parse::<T>()
+            .map_err(|_| format!("invalid {} '{}'", column, value))
+    }
+}
+
+impl ProcessSource for FixtureSource {
+    fn enumerate(&mut self) -> io::Result<Sample> {
+        Ok(Sample::from_procs(self.procs.clone()))
+    }
+
+    fn signal(&mut self, proc: &Process, signal: Signal) -> io::Result<()> {
+        let Some(spot) = self.procs
+            .iter()
+            .position(|p| p.is_same_process(proc)) else {
+            return Err(io::Error::new(io::ErrorKind::NotFound, "no such process"));
+        };
+
+        if self.procs[spot].user == "root" {
+            return Err(io::Error::new(io::ErrorKind::PermissionDenied, "operation not permitted"));
+        }
+
+        if signal != Signal::STOP && signal != Signal::CONT {
+            self.procs.remove(spot);
+        }
+        Ok(())
+    }
+}
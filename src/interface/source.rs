@@ -0,0 +1,367 @@
+use std::io;
+#[cfg(any(target_os = "macos", target_os = "windows"))]
+use std::process::Command;
+#[cfg(target_os = "linux")]
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use super::{Process, ProcessDetails};
+use crate::signal::Signal;
+
+#[cfg(target_os = "linux")]
+use crate::{pidfd::{self, PidFd}, procfs};
+
+// Where the monitor gets its processes from and how it signals them. The
+// system sources talk to the OS, the fixture source lives entirely in memory.
+pub trait ProcessSource: Send {
+    fn enumerate(&mut self) -> io::Result<Sample>;
+    fn signal(&mut self, proc: &Process, signal: Signal) -> io::Result<()>;
+
+    // Whether the process from an earlier sample is still running, sources
+    // that can check a single process cheaply should override this
+    fn is_alive(&mut self, proc: &Process) -> bool {
+        self.enumerate()
+            .map(|sample| sample.procs.iter().any(|p| p.is_same_process(proc)))
+            .unwrap_or(false)
+    }
+
+    // A closer look at a single process for the detail pane, by default
+    // only what the process list already has
+    fn details(&mut self, proc: &Process) -> io::Result<ProcessDetails> {
+        Ok(ProcessDetails::from_process(proc))
+    }
+}
+
+pub struct Sample {
+    pub procs: Vec<Process>,
+    // Jiffies spent by all cpus since boot, None if the source already
+    // reports a usable cpu percentage for each process
+    pub total_time: Option<u64>,
+    pub num_cpus: usize,
+}
+
+impl Sample {
+    pub fn from_procs(procs: Vec<Process>) -> Self {
+        Self {
+            procs,
+            total_time: None,
+            num_cpus: 1,
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+pub type SystemSource = ProcfsSource;
+#[cfg(target_os = "macos")]
+pub type SystemSource = PsSource;
+#[cfg(target_os = "windows")]
+pub type SystemSource = TasklistSource;
+
+#[cfg(target_os = "linux")]
+pub struct ProcfsSource;
+
+#[cfg(target_os = "linux")]
+impl ProcessSource for ProcfsSource {
+    fn enumerate(&mut self) -> io::Result<Sample> {
+        // Walk /proc directly instead of spawning ps, processes that exit
+        // while we are reading them are simply skipped
+        let pids = procfs::list_pids()?;
+
+        let mem_total = procfs::read_mem_total().unwrap_or(0) as f64;
+        let uptime = procfs::read_uptime().unwrap_or(0.0);
+        let ticks = procfs::clock_ticks() as f64;
+        let page_size = procfs::page_size();
+        let cpu_times = procfs::read_cpu_times().ok();
+        let user_names = procfs::read_user_names();
+
+        let procs = pids
+            .into_iter()
+            .filter_map(|pid| {
+                let stat = procfs::read_stat(pid).ok()?;
+                let statm = procfs::read_statm(pid).ok()?;
+                let status = procfs::read_status(pid).ok()?;
+
+                let mut p = Process::new();
+                p.command = stat.comm;
+                p.pid = pid;
+                p.start_time = stat.starttime;
+                p.ppid = stat.ppid;
+                p.state = stat.state;
+                p.cmdline = procfs::read_cmdline(pid)
+                    .unwrap_or_default()
+                    .join(" ");
+                p.exe = procfs::read_exe(pid)
+                    .map(|path| path.to_string_lossy().into_owned())
+                    .unwrap_or_default();
+                p.user = user_names
+                    .get(&status.uid)
+                    .cloned()
+                    .unwrap_or_else(|| status.uid.to_string());
+
+                if mem_total > 0.0 {
+                    p.mem = ((statm.resident * page_size) as f64 / mem_total * 100.0) as f32;
+                }
+
+                // Until the monitor has a previous sample to diff against,
+                // fall back to the lifetime average like ps does
+                p.cpu_time = stat.utime + stat.stime;
+                let elapsed = uptime - stat.starttime as f64 / ticks;
+                if elapsed > 0.0 {
+                    p.cpu = (p.cpu_time as f64 / ticks / elapsed * 100.0) as f32;
+                }
+
+                Some(p)
+            })
+            .collect();
+
+        Ok(Sample {
+            procs,
+            total_time: cpu_times.as_ref().map(|times| times.total),
+            num_cpus: cpu_times.map(|times| times.num_cpus).unwrap_or(1),
+        })
+    }
+
+    fn signal(&mut self, proc: &Process, signal: Signal) -> io::Result<()> {
+        // Pin the process with a pidfd before checking its start time, once
+        // the pidfd is open the pid can't be handed to another process, so a
+        // matching start time means the signal reaches the one we displayed
+        match PidFd::open(proc.pid) {
+            Ok(pidfd) => {
+                verify_start_time(proc)?;
+                pidfd.send_signal(signal.number())
+            }
+            Err(err) if err.raw_os_error() == Some(libc::ENOSYS) => {
+                verify_start_time(proc)?;
+                pidfd::kill(proc.pid, signal.number())
+            }
+            Err(err) => Err(err),
+        }
+    }
+
+    // A zombie has already exited, it's only waiting on its parent to reap it
+    fn is_alive(&mut self, proc: &Process) -> bool {
+        procfs::read_stat(proc.pid)
+            .map(|stat| stat.starttime == proc.start_time && stat.state != 'Z')
+            .unwrap_or(false)
+    }
+
+    // The files that only the owner or root can read are left as None for
+    // everyone else's processes
+    fn details(&mut self, proc: &Process) -> io::Result<ProcessDetails> {
+        verify_start_time(proc)?;
+        let stat = procfs::read_stat(proc.pid)?;
+        let page_size = procfs::page_size();
+        let mut details = ProcessDetails::from_process(proc);
+
+        details.state = stat.state;
+        details.threads = Some(stat.num_threads);
+        details.nice = Some(stat.nice);
+        details.priority = Some(stat.priority);
+
+        if let Ok(statm) = procfs::read_statm(proc.pid) {
+            details.rss = Some(statm.resident * page_size);
+            details.vsz = Some(statm.size * page_size);
+        }
+        if let Ok(status) = procfs::read_status(proc.pid) {
+            details.group = Some(
+                procfs::read_group_names()
+                    .remove(&status.gid)
+                    .unwrap_or_else(|| status.gid.to_string())
+            );
+        }
+
+        let started = stat.starttime as f64 / procfs::clock_ticks() as f64;
+        if let Ok(uptime) = procfs::read_uptime() {
+            let running_for = Duration::from_secs_f64((uptime - started).max(0.0));
+            details.running_for = Some(running_for);
+            details.start_time = SystemTime::now()
+                .checked_sub(running_for)
+                .and_then(local_time);
+        }
+
+        details.exe = procfs::read_exe(proc.pid)
+            .ok()
+            .map(|path| path.to_string_lossy().into_owned())
+            .or(details.exe);
+        details.cwd = procfs::read_cwd(proc.pid)
+            .ok()
+            .map(|path| path.to_string_lossy().into_owned());
+        details.open_files = procfs::count_open_files(proc.pid).ok();
+        details.cgroups = procfs::read_cgroups(proc.pid).ok();
+        details.environ = procfs::read_environ(proc.pid).ok();
+
+        Ok(details)
+    }
+}
+
+// Like 2024-05-01 13:37:00 in the local time zone
+#[cfg(target_os = "linux")]
+fn local_time(time: SystemTime) -> Option<String> {
+    let secs = time.duration_since(UNIX_EPOCH).ok()?.as_secs() as libc::time_t;
+    // SAFETY: tm is only read after localtime_r filled it in
+    let mut tm: libc::tm = unsafe { std::mem::zeroed() };
+    if unsafe { libc::localtime_r(&secs, &mut tm) }.is_null() {
+        return None;
+    }
+
+    Some(format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02}",
+        tm.tm_year + 1900, tm.tm_mon + 1, tm.tm_mday, tm.tm_hour, tm.tm_min, tm.tm_sec
+    ))
+}
+
+// Make sure the pid still belongs to the process from the snapshot and
+// hasn't been recycled for something else since
+#[cfg(target_os = "linux")]
+fn verify_start_time(proc: &Process) -> io::Result<()> {
+    let stat = procfs::read_stat(proc.pid)?;
+
+    if stat.starttime != proc.start_time {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("pid {} now belongs to a different process", proc.pid)
+        ));
+    }
+
+    Ok(())
+}
+
+#[cfg(target_os = "macos")]
+pub struct PsSource;
+
+#[cfg(target_os = "macos")]
+impl ProcessSource for PsSource {
+    fn enumerate(&mut self) -> io::Result<Sample> {
+        // Get the current list of processes
+        let output = Command::new("ps")
+            .args("-A --format comm,pid,%mem,%cpu,ppid,state,args".split(" "))
+            .output()?;
+
+        // Check to see if the command executed successfully
+        if !output.status.success() {
+            return Err(io::Error::other("ps exited unsuccessfully"));
+        }
+        let res = String::from_utf8(output.stdout)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+
+        Ok(Sample::from_procs(res.lines().skip(1).map(|line| {
+            let mut p: Process = Process::new();
+            let mut args: Vec<&str> = Vec::new();
+
+            line.split_ascii_whitespace().enumerate().for_each(|(i, col)| {
+                match i {
+                    0 => p.command = col.to_string(),
+                    1 => p.pid = col.parse::<u64>().unwrap_or(u64::MAX),
+                    2 => p.mem = col.parse::<f32>().unwrap_or(0.0),
+                    3 => p.cpu = col.parse::<f32>().unwrap_or(0.0),
+                    4 => p.ppid = col.parse::<u64>().unwrap_or(0),
+                    5 => p.state = col.chars().next().unwrap_or('?'),
+                    // The arguments are last since they contain spaces themselves
+                    _ => args.push(col),
+                }
+            });
+            p.cmdline = args.join(" ");
+
+            p
+        }).collect()))
+    }
+
+    // Signal 0 only checks that the process exists, rather than running ps.
+    // A zombie still counts as alive here until its parent reaps it
+    fn is_alive(&mut self, proc: &Process) -> bool {
+        let Ok(pid) = libc::pid_t::try_from(proc.pid) else {
+            return false;
+        };
+        // SAFETY: kill has no preconditions and signal 0 sends nothing
+        if unsafe { libc::kill(pid, 0) } == 0 {
+            return true;
+        }
+        // Someone else's process that is still running
+        io::Error::last_os_error().raw_os_error() == Some(libc::EPERM)
+    }
+
+    fn signal(&mut self, proc: &Process, signal: Signal) -> io::Result<()> {
+        run_kill_command(
+            Command::new("kill")
+                .arg(format!("-{}", signal.number()))
+                .arg(proc.pid.to_string())
+        )
+    }
+}
+
+#[cfg(target_os = "windows")]
+pub struct TasklistSource;
+
+#[cfg(target_os = "windows")]
+impl ProcessSource for TasklistSource {
+    fn enumerate(&mut self) -> io::Result<Sample> {
+        // Get the current list of processes
+        let output = Command::new("tasklist")
+            .args("/NH /FO TABLE".split(" "))
+            .output()?;
+
+        // Check to see if the command executed successfully
+        if !output.status.success() {
+            return Err(io::Error::other("tasklist exited unsuccessfully"));
+        }
+        let res = String::from_utf8(output.stdout)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+
+        Ok(Sample::from_procs(res.lines().filter_map(|line| {
+            // Iterate over every task and insert the process into the vector attached to that command (includes children)
+            let mut p: Process = Process::new();
+            let mut units: &str = "";
+
+            // The columns are gotten from TABLE format in tasklist
+            line.split_ascii_whitespace()
+                .enumerate()
+                .for_each(|(i, col)| {
+                    match i {
+                        0 => p.command = col.to_string(),
+                        1 => p.pid = col.parse::<u64>().unwrap_or(u64::MAX),
+                        4 => p.mem = col.to_string(),
+                        5 => units = col,
+                        _ => (),
+                    }
+                });
+
+            if p.pid == u64::MAX {
+                return None;
+            }
+
+            // Add the bytes units to the number
+            p.mem.push(' ');
+            p.mem.push_str(units);
+            p.mem.push_str("iB");
+            Some(p)
+        }).collect()))
+    }
+
+    // Windows has no signals, every signal force ends the process tree
+    fn signal(&mut self, proc: &Process, _signal: Signal) -> io::Result<()> {
+        run_kill_command(
+            Command::new("taskkill")
+                .arg("/T")
+                .arg("/F")
+                .arg("/PID")
+                .arg(proc.pid.to_string())
+        )
+    }
+}
+
+#[cfg(any(target_os = "macos", target_os = "windows"))]
+fn run_kill_command(command: &mut Command) -> io::Result<()> {
+    let output = command.output()?;
+
+    if !output.status.success() {
+        let msg = String::from_utf8_lossy(&output.stderr).trim().to_string();
+        // Keep the kind so callers can tell a lack of privileges apart
+        let kind = if msg.contains("not permitted") || msg.contains("Access is denied") {
+            io::ErrorKind::PermissionDenied
+        } else {
+            io::ErrorKind::Other
+        };
+        return Err(io::Error::new(kind, msg));
+    }
+
+    Ok(())
+}
@@ -0,0 +1,688 @@
+use std::{collections::HashMap, fmt};
+
+use ratatui::crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+use crate::signal::Signal;
+
+// Everything a key can do in the interface besides typing into the search
+// box. The order here is the order the help screen and footer list them in
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Action {
+    Help,
+    Quit,
+    NormalMode,
+    InsertMode,
+    Pick,
+    CancelPick,
+    Kill,
+    SendSignal,
+    Terminate,
+    KillByName,
+    KillTree,
+    ToggleTree,
+    Details,
+    Environment,
+    NextSortColumn,
+    ReverseSort,
+    SortMatches,
+    SearchCmdline,
+    NextSearchMode,
+    ToggleCase,
+    Mark,
+    MarkAll,
+    InvertMarks,
+    ClearSearch,
+    ResetScroll,
+    MoveUp,
+    MoveDown,
+    HalfPageUp,
+    HalfPageDown,
+    Top,
+    Bottom,
+    Collapse,
+    Expand,
+}
+
+// Which way keys are read in vim mode. Insert types into the search box like
+// the interface always does without vim mode, normal uses plain keys and
+// sequences of them for moving around and acting on processes
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Mode {
+    Insert,
+    Normal,
+}
+
+impl Mode {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Insert => "INSERT",
+            Self::Normal => "NORMAL",
+        }
+    }
+}
+
+// What a sequence of keys pressed in normal mode adds up to so far
+pub enum Lookup {
+    Action(Action),
+    // The start of a longer sequence, like the first d of dd, with the keys
+    // pressed so far
+    Pending(Vec<Key>),
+    None,
+}
+
+impl Action {
+    pub const ALL: [Action; 33] = [
+        Self::Help,
+        Self::Quit,
+        Self::NormalMode,
+        Self::InsertMode,
+        Self::Pick,
+        Self::CancelPick,
+        Self::Kill,
+        Self::SendSignal,
+        Self::Terminate,
+        Self::KillByName,
+        Self::KillTree,
+        Self::ToggleTree,
+        Self::Details,
+        Self::Environment,
+        Self::NextSortColumn,
+        Self::ReverseSort,
+        Self::SortMatches,
+        Self::SearchCmdline,
+        Self::NextSearchMode,
+        Self::ToggleCase,
+        Self::Mark,
+        Self::MarkAll,
+        Self::InvertMarks,
+        Self::ClearSearch,
+        Self::ResetScroll,
+        Self::MoveUp,
+        Self::MoveDown,
+        Self::HalfPageUp,
+        Self::HalfPageDown,
+        Self::Top,
+        Self::Bottom,
+        Self::Collapse,
+        Self::Expand,
+    ];
+
+    // What the action is called in the [keybinds] and [normal-keybinds] tables
+    // of the config file
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Help => "help",
+            Self::Quit => "quit",
+            Self::NormalMode => "normal-mode",
+            Self::InsertMode => "insert-mode",
+            Self::Pick => "pick",
+            Self::CancelPick => "cancel-pick",
+            Self::Kill => "kill",
+            Self::SendSignal => "send-signal",
+            Self::Terminate => "terminate",
+            Self::KillByName => "kill-by-name",
+            Self::KillTree => "kill-tree",
+            Self::ToggleTree => "tree-view",
+            Self::Details => "details",
+            Self::Environment => "environment",
+            Self::NextSortColumn => "sort-column",
+            Self::ReverseSort => "reverse-sort",
+            Self::SortMatches => "sort-matches",
+            Self::SearchCmdline => "search-cmdline",
+            Self::NextSearchMode => "search-mode",
+            Self::ToggleCase => "case-sensitive",
+            Self::Mark => "mark",
+            Self::MarkAll => "mark-all",
+            Self::InvertMarks => "invert-marks",
+            Self::ClearSearch => "clear-search",
+            Self::ResetScroll => "reset-scroll",
+            Self::MoveUp => "up",
+            Self::MoveDown => "down",
+            Self::HalfPageUp => "half-page-up",
+            Self::HalfPageDown => "half-page-down",
+            Self::Top => "top",
+            Self::Bottom => "bottom",
+            Self::Collapse => "collapse",
+            Self::Expand => "expand",
+        }
+    }
+
+    pub fn parse(name: &str) -> Result<Self, String> {
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|action| action.name() == name)
+            .ok_or_else(|| {
+                let names = Self::ALL
+                    .iter()
+                    .map(|action| action.name())
+                    .collect::<Vec<&str>>()
+                    .join(", ");
+                format!("unknown action '{}', expected one of {}", name, names)
+            })
+    }
+
+    fn description(self, default_signal: Signal) -> String {
+        let text = match self {
+            Self::Help => "help",
+            Self::Quit => "quit",
+            Self::NormalMode => "normal mode",
+            Self::InsertMode => "search",
+            Self::Pick => "pick process",
+            Self::CancelPick => "pick nothing and quit",
+            Self::Kill => return format!("kill process ({})", default_signal),
+            Self::SendSignal => "send signal",
+            Self::Terminate => "terminate (TERM, then KILL)",
+            Self::KillByName => "kill all with this name",
+            Self::KillTree => "kill process tree",
+            Self::ToggleTree => "toggle tree view",
+            Self::Details => "toggle process details",
+            Self::Environment => "show the environment in the details",
+            Self::NextSortColumn => "change sort column",
+            Self::ReverseSort => "reverse sort",
+            Self::SortMatches => "sort matches by column",
+            Self::SearchCmdline => "search command lines",
+            Self::NextSearchMode => "change search mode",
+            Self::ToggleCase => "toggle case sensitivity",
+            Self::Mark => "mark process",
+            Self::MarkAll => "mark all",
+            Self::InvertMarks => "invert marks",
+            Self::ClearSearch => "clear search",
+            Self::ResetScroll => "reset scroll",
+            Self::MoveUp => "move up",
+            Self::MoveDown => "move down",
+            Self::HalfPageUp => "move up half a page",
+            Self::HalfPageDown => "move down half a page",
+            Self::Top => "go to the top",
+            Self::Bottom => "go to the bottom",
+            Self::Collapse => "collapse a process in the tree view",
+            Self::Expand => "expand a process in the tree view",
+        };
+        text.to_string()
+    }
+
+    // Whether the action does anything in pick mode or outside of it, and
+    // in the current vim mode if vim mode is on
+    fn is_available(self, pick: bool, mode: Option<Mode>) -> bool {
+        match self {
+            Self::NormalMode => mode == Some(Mode::Insert),
+            Self::InsertMode => mode == Some(Mode::Normal),
+            Self::Pick | Self::CancelPick => pick,
+            Self::Kill | Self::SendSignal | Self::Terminate | Self::KillByName | Self::KillTree => !pick,
+            _ => true,
+        }
+    }
+
+    // The footer is a single line, so it only has room for the core actions
+    // and the rest are listed on the help screen
+    fn in_footer(self) -> bool {
+        matches!(self, Self::Help | Self::Quit | Self::NormalMode | Self::InsertMode | Self::Pick | Self::Kill | Self::ClearSearch)
+    }
+}
+
+// A key with its modifiers, written like ctrl+k, alt+enter or f5
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Key {
+    code: KeyCode,
+    modifiers: KeyModifiers,
+}
+
+impl Key {
+    const fn new(code: KeyCode, modifiers: KeyModifiers) -> Self {
+        Self { code, modifiers }
+    }
+
+    const fn ctrl(c: char) -> Self {
+        Self::new(KeyCode::Char(c), KeyModifiers::CONTROL)
+    }
+
+    const fn plain(code: KeyCode) -> Self {
+        Self::new(code, KeyModifiers::NONE)
+    }
+
+    const fn char(c: char) -> Self {
+        Self::plain(KeyCode::Char(c))
+    }
+
+    // The key as it is compared against normal mode sequences, where g and G
+    // are different keys. The case of a letter already says whether shift was
+    // held, so shift only counts for keys that aren't characters
+    fn from_event(event: &KeyEvent) -> Self {
+        match event.code {
+            KeyCode::Char(c) => Self::new(KeyCode::Char(c), event.modifiers.difference(KeyModifiers::SHIFT)),
+            code => Self::new(code, event.modifiers),
+        }
+    }
+
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let text = text.trim();
+        // A trailing '+' is the plus key itself, as in ctrl++
+        let (mods, name) = match text.strip_suffix("++") {
+            Some(mods) => (mods, "+"),
+            None => text.rsplit_once('+').unwrap_or(("", text)),
+        };
+
+        let mut modifiers = KeyModifiers::NONE;
+        for modifier in mods.split('+').filter(|m| !m.is_empty()) {
+            modifiers |= match modifier.to_ascii_lowercase().as_str() {
+                "ctrl" | "control" => KeyModifiers::CONTROL,
+                "alt" | "meta" => KeyModifiers::ALT,
+                "shift" => KeyModifiers::SHIFT,
+                _ => return Err(format!("unknown modifier '{}' in '{}'", modifier, text)),
+            };
+        }
+
+        let mut chars = name.chars();
+        let code = match (chars.next(), chars.next()) {
+            // Letters keep their case, G is not g in normal mode
+            (Some(c), None) => KeyCode::Char(c),
+            _ => match name.to_ascii_lowercase().as_str() {
+                "enter" | "return" => KeyCode::Enter,
+                "esc" | "escape" => KeyCode::Esc,
+                "tab" => KeyCode::Tab,
+                "backtab" => KeyCode::BackTab,
+                "backspace" => KeyCode::Backspace,
+                "delete" | "del" => KeyCode::Delete,
+                "insert" => KeyCode::Insert,
+                "home" => KeyCode::Home,
+                "end" => KeyCode::End,
+                "pageup" => KeyCode::PageUp,
+                "pagedown" => KeyCode::PageDown,
+                "up" => KeyCode::Up,
+                "down" => KeyCode::Down,
+                "left" => KeyCode::Left,
+                "right" => KeyCode::Right,
+                "space" => KeyCode::Char(' '),
+                lower => match lower.strip_prefix('f').and_then(|n| n.parse::<u8>().ok()) {
+                    Some(n @ 1..=12) => KeyCode::F(n),
+                    _ => return Err(format!("unknown key '{}' in '{}'", name, text)),
+                },
+            },
+        };
+
+        Ok(Self::new(code, modifiers))
+    }
+
+    // A sequence for normal mode, either keys separated by spaces like
+    // "ctrl+w j" or a run of plain characters like "dd" or "gg"
+    fn parse_sequence(text: &str) -> Result<Vec<Self>, String> {
+        let text = text.trim();
+        if text.contains(char::is_whitespace) {
+            return text.split_whitespace().map(Self::parse).collect();
+        }
+
+        match Self::parse(text) {
+            Ok(key) => Ok(vec![key]),
+            Err(_) if !text.is_empty() && !text.contains('+') => Ok(text.chars().map(Self::char).collect()),
+            Err(err) => Err(err),
+        }
+    }
+
+    // Outside of normal mode letters match either case, since terminals
+    // report ctrl+k as K when shift or caps lock is on
+    pub fn matches(&self, event: &KeyEvent) -> bool {
+        let pressed = Self::from_event(event);
+
+        match (self.code, pressed.code) {
+            (KeyCode::Char(bound), KeyCode::Char(c)) => {
+                bound.eq_ignore_ascii_case(&c)
+                    && self.modifiers.difference(KeyModifiers::SHIFT) == pressed.modifiers
+            },
+            _ => *self == pressed,
+        }
+    }
+
+    fn types_text(&self) -> bool {
+        matches!(self.code, KeyCode::Char(_))
+            && self.modifiers.difference(KeyModifiers::SHIFT).is_empty()
+    }
+}
+
+impl fmt::Display for Key {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (modifier, name) in [(KeyModifiers::CONTROL, "ctrl+"), (KeyModifiers::ALT, "alt+"), (KeyModifiers::SHIFT, "shift+")] {
+            if self.modifiers.contains(modifier) {
+                f.write_str(name)?;
+            }
+        }
+
+        match self.code {
+            KeyCode::Char(' ') => f.write_str("space"),
+            KeyCode::Char(c) => write!(f, "{}", c),
+            KeyCode::F(n) => write!(f, "f{}", n),
+            KeyCode::Up => f.write_str("\u{2191}"),
+            KeyCode::Down => f.write_str("\u{2193}"),
+            KeyCode::Left => f.write_str("\u{2190}"),
+            KeyCode::Right => f.write_str("\u{2192}"),
+            KeyCode::Enter => f.write_str("enter"),
+            KeyCode::Esc => f.write_str("esc"),
+            KeyCode::Tab => f.write_str("tab"),
+            KeyCode::BackTab => f.write_str("backtab"),
+            KeyCode::Backspace => f.write_str("backspace"),
+            KeyCode::Delete => f.write_str("delete"),
+            KeyCode::Insert => f.write_str("insert"),
+            KeyCode::Home => f.write_str("home"),
+            KeyCode::End => f.write_str("end"),
+            KeyCode::PageUp => f.write_str("pageup"),
+            KeyCode::PageDown => f.write_str("pagedown"),
+            code => write!(f, "{:?}", code),
+        }
+    }
+}
+
+// Plain characters run together like vim writes them, anything else is
+// spaced out
+fn sequence_text(keys: &[Key]) -> String {
+    if keys.iter().all(Key::types_text) {
+        keys.iter().map(Key::to_string).collect()
+    } else {
+        keys.iter().map(Key::to_string).collect::<Vec<String>>().join(" ")
+    }
+}
+
+// Which keys trigger which action. The help screen and the footer are both
+// generated from this, so they always show what the keys actually do.
+// Normal mode is looked up first while vim mode is in it, anything it
+// doesn't bind falls through to the regular bindings
+#[derive(Clone, Debug)]
+pub struct Keymap {
+    bindings: Vec<(Action, Vec<Key>)>,
+    normal: Vec<(Action, Vec<Vec<Key>>)>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        let bindings = Action::ALL
+            .iter()
+            .map(|&action| {
+                let keys = match action {
+                    Action::Help => vec![Key::ctrl('h')],
+                    Action::Quit => vec![Key::ctrl('q'), Key::ctrl('c')],
+                    Action::NormalMode => vec![Key::plain(KeyCode::Esc)],
+                    // Only bound in normal mode
+                    Action::InsertMode => Vec::new(),
+                    Action::Pick => vec![Key::plain(KeyCode::Enter)],
+                    Action::CancelPick => vec![Key::plain(KeyCode::Esc)],
+                    Action::Kill => vec![Key::ctrl('k')],
+                    Action::SendSignal => vec![Key::ctrl('s')],
+                    Action::Terminate => vec![Key::ctrl('t')],
+                    Action::KillByName => vec![Key::ctrl('n')],
+                    Action::KillTree => vec![Key::ctrl('e')],
+                    Action::ToggleTree => vec![Key::ctrl('v')],
+                    Action::Details => vec![Key::ctrl('p')],
+                    Action::Environment => vec![Key::ctrl('g')],
+                    Action::NextSortColumn => vec![Key::ctrl('o')],
+                    Action::ReverseSort => vec![Key::ctrl('u')],
+                    Action::SortMatches => vec![Key::ctrl('w')],
+                    Action::SearchCmdline => vec![Key::ctrl('l')],
+                    Action::NextSearchMode => vec![Key::ctrl('f')],
+                    Action::ToggleCase => vec![Key::ctrl('y')],
+                    // Not space, which separates the words of a query. Normal
+                    // mode marks with space since nothing is typed there, and
+                    // mark = "space" under [keybinds] works everywhere else
+                    // for anyone who doesn't need it in searches
+                    Action::Mark => vec![Key::plain(KeyCode::Tab)],
+                    Action::MarkAll => vec![Key::ctrl('a')],
+                    Action::InvertMarks => vec![Key::ctrl('x')],
+                    Action::ClearSearch => vec![Key::ctrl('b')],
+                    Action::ResetScroll => vec![Key::ctrl('r')],
+                    Action::MoveUp => vec![Key::plain(KeyCode::Up)],
+                    Action::MoveDown => vec![Key::plain(KeyCode::Down)],
+                    Action::HalfPageUp => vec![Key::plain(KeyCode::PageUp)],
+                    Action::HalfPageDown => vec![Key::plain(KeyCode::PageDown)],
+                    Action::Top => vec![Key::plain(KeyCode::Home)],
+                    Action::Bottom => vec![Key::plain(KeyCode::End)],
+                    Action::Collapse => vec![Key::plain(KeyCode::Left)],
+                    Action::Expand => vec![Key::plain(KeyCode::Right)],
+                };
+                (action, keys)
+            })
+            .collect();
+
+        let normal = [
+            (Action::Help, vec!["?"]),
+            (Action::Quit, vec!["q"]),
+            (Action::InsertMode, vec!["/", "i"]),
+            (Action::Kill, vec!["dd"]),
+            (Action::Terminate, vec!["D"]),
+            (Action::Mark, vec!["space", "x"]),
+            (Action::Details, vec!["K"]),
+            (Action::MoveUp, vec!["k"]),
+            (Action::MoveDown, vec!["j"]),
+            (Action::HalfPageUp, vec!["ctrl+u"]),
+            (Action::HalfPageDown, vec!["ctrl+d"]),
+            (Action::Top, vec!["gg"]),
+            (Action::Bottom, vec!["G"]),
+            (Action::Collapse, vec!["h"]),
+            (Action::Expand, vec!["l"]),
+        ]
+        .into_iter()
+        .map(|(action, texts)| {
+            let sequences = texts
+                .into_iter()
+                .filter_map(|text| Key::parse_sequence(text).ok())
+                .collect();
+            (action, sequences)
+        })
+        .collect();
+
+        Self { bindings, normal }
+    }
+}
+
+impl Keymap {
+    // Applies the [keybinds] and [normal-keybinds] tables from the config
+    // file on top of the defaults. Each entry replaces every default key of
+    // its action, and a key taken by an entry stops doing whatever it did by
+    // default
+    pub fn with_overrides(overrides: &[(String, Vec<String>)], normal_overrides: &[(String, Vec<String>)]) -> Result<Self, String> {
+        let mut keymap = Self::default();
+        let mut taken: HashMap<Key, Action> = HashMap::new();
+
+        for (name, texts) in overrides {
+            let action = Action::parse(name)?;
+            let keys = texts
+                .iter()
+                .map(|text| {
+                    let key = Key::parse(text)?;
+                    // Space is let through for people who'd rather mark with
+                    // it than type multi-word queries
+                    if key.types_text() && key.code != KeyCode::Char(' ') {
+                        return Err(format!("'{}' would type into the search box, add a modifier like ctrl+{}", text, text.trim()));
+                    }
+                    Ok(key)
+                })
+                .collect::<Result<Vec<Key>, String>>()
+                .map_err(|err| format!("keybind for '{}': {}", name, err))?;
+
+            for &key in &keys {
+                if let Some(other) = taken.insert(key, action).filter(|&other| other != action) {
+                    return Err(format!("'{}' is bound to both '{}' and '{}'", key, other.name(), name));
+                }
+            }
+
+            keymap.bindings
+                .iter_mut()
+                .for_each(|(_, bound)| bound.retain(|key| !keys.contains(key)));
+            if let Some((_, bound)) = keymap.bindings.iter_mut().find(|(a, _)| *a == action) {
+                *bound = keys;
+            }
+        }
+
+        let mut taken: HashMap<Vec<Key>, Action> = HashMap::new();
+        for (name, texts) in normal_overrides {
+            let action = Action::parse(name)?;
+            let sequences = texts
+                .iter()
+                .map(|text| Key::parse_sequence(text))
+                .collect::<Result<Vec<Vec<Key>>, String>>()
+                .map_err(|err| format!("normal mode keybind for '{}': {}", name, err))?;
+
+            for sequence in &sequences {
+                if let Some(other) = taken.insert(sequence.clone(), action).filter(|&other| other != action) {
+                    return Err(format!("'{}' is bound to both '{}' and '{}' in normal mode", sequence_text(sequence), other.name(), name));
+                }
+            }
+
+            keymap.normal
+                .iter_mut()
+                .for_each(|(_, bound)| bound.retain(|sequence| !sequences.contains(sequence)));
+            match keymap.normal.iter_mut().find(|(a, _)| *a == action) {
+                Some((_, bound)) => *bound = sequences,
+                None => keymap.normal.push((action, sequences)),
+            }
+        }
+
+        Ok(keymap)
+    }
+
+    // The action bound to the key that was pressed. Some keys do different
+    // things depending on the mode, so only actions that are available count.
+    // mode is None when vim mode is off
+    pub fn action(&self, event: &KeyEvent, pick: bool, mode: Option<Mode>) -> Option<Action> {
+        self.bindings
+            .iter()
+            .filter(|(action, _)| action.is_available(pick, mode))
+            .find(|(_, keys)| keys.iter().any(|key| key.matches(event)))
+            .map(|&(action, _)| action)
+    }
+
+    // Looks up the keys pressed in normal mode so far, the last one being
+    // event. A single key that isn't bound in normal mode falls through to
+    // the regular bindings
+    pub fn normal_action(&self, pending: &[Key], event: &KeyEvent, pick: bool) -> Lookup {
+        let mut sequence = pending.to_vec();
+        sequence.push(Key::from_event(event));
+
+        let available = self.normal
+            .iter()
+            .filter(|(action, _)| action.is_available(pick, Some(Mode::Normal)));
+        let mut pending = false;
+        for (action, sequences) in available {
+            for bound in sequences {
+                if *bound == sequence {
+                    return Lookup::Action(*action);
+                }
+                pending |= bound.starts_with(&sequence);
+            }
+        }
+
+        if pending {
+            Lookup::Pending(sequence)
+        } else if sequence.len() == 1 {
+            self.action(event, pick, Some(Mode::Normal))
+                .map(Lookup::Action)
+                .unwrap_or(Lookup::None)
+        } else {
+            Lookup::None
+        }
+    }
+
+    pub fn is_bound(&self, event: &KeyEvent, action: Action) -> bool {
+        self.bindings
+            .iter()
+            .any(|(a, keys)| *a == action && keys.iter().any(|key| key.matches(event)))
+    }
+
+    // The first regular key bound to action, for pointing at it in the interface
+    pub fn key_for(&self, action: Action) -> Option<String> {
+        self.bindings
+            .iter()
+            .find(|(a, _)| *a == action)
+            .and_then(|(_, keys)| keys.first())
+            .map(Key::to_string)
+    }
+
+    // One "[keys] description" entry per bound action, help_screen lists
+    // every action instead of just the ones in the footer
+    pub fn hints(&self, pick: bool, default_signal: Signal, help_screen: bool, mode: Option<Mode>) -> Vec<String> {
+        let normal = |action: Action| {
+            self.normal
+                .iter()
+                .filter(|(a, _)| *a == action && mode == Some(Mode::Normal))
+                .flat_map(|(_, sequences)| sequences.iter().map(|sequence| sequence_text(sequence)))
+                .collect::<Vec<String>>()
+        };
+        // Regular keys that normal mode took over don't do their usual thing there
+        let shadowed = |key: &Key| {
+            mode == Some(Mode::Normal)
+                && self.normal
+                    .iter()
+                    .any(|(_, sequences)| sequences.iter().any(|sequence| sequence.as_slice() == [*key]))
+        };
+
+        Action::ALL
+            .iter()
+            .filter(|action| action.is_available(pick, mode) && (help_screen || action.in_footer()))
+            .filter_map(|&action| {
+                let mut keys = normal(action);
+                self.bindings
+                    .iter()
+                    .filter(|(a, _)| *a == action)
+                    .flat_map(|(_, bound)| bound.iter().filter(|key| !shadowed(key)))
+                    .for_each(|key| keys.push(key.to_string()));
+                if keys.is_empty() {
+                    return None;
+                }
+
+                let description = match action {
+                    Action::Help if help_screen => String::from("exit this screen"),
+                    _ => action.description(default_signal),
+                };
+                Some(format!("[{}] {}", keys.join("|"), description))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn overrides(name: &str, key: &str) -> Vec<(String, Vec<String>)> {
+        vec![(name.to_string(), vec![key.to_string()])]
+    }
+
+    #[test]
+    fn parse_keys() {
+        assert_eq!(Key::parse("ctrl+k"), Ok(Key::ctrl('k')));
+        assert_eq!(Key::parse(" Control+Alt+Up "), Ok(Key::new(KeyCode::Up, KeyModifiers::CONTROL | KeyModifiers::ALT)));
+        assert_eq!(Key::parse("ctrl++"), Ok(Key::ctrl('+')));
+        assert_eq!(Key::parse("G"), Ok(Key::char('G')));
+        assert_eq!(Key::parse("space"), Ok(Key::char(' ')));
+        assert_eq!(Key::parse("f12"), Ok(Key::plain(KeyCode::F(12))));
+        assert!(Key::parse("f13").is_err());
+        assert!(Key::parse("hyper+k").is_err());
+        assert!(Key::parse("ctrl+nope").is_err());
+    }
+
+    #[test]
+    fn parse_sequences() {
+        assert_eq!(Key::parse_sequence("gg"), Ok(vec![Key::char('g'), Key::char('g')]));
+        assert_eq!(Key::parse_sequence("ctrl+w j"), Ok(vec![Key::ctrl('w'), Key::char('j')]));
+    }
+
+    #[test]
+    fn space_marks_in_normal_mode() {
+        let keymap = Keymap::default();
+        let space = KeyEvent::new(KeyCode::Char(' '), KeyModifiers::NONE);
+        assert!(matches!(keymap.normal_action(&[], &space, false), Lookup::Action(Action::Mark)));
+        assert_eq!(keymap.action(&space, false, Some(Mode::Insert)), None);
+    }
+
+    #[test]
+    fn overrides_replace_defaults() {
+        let keymap = Keymap::with_overrides(&overrides("kill", "ctrl+d"), &[]).unwrap();
+        let event = KeyEvent::new(KeyCode::Char('d'), KeyModifiers::CONTROL);
+        assert_eq!(keymap.action(&event, false, None), Some(Action::Kill));
+        let old = KeyEvent::new(KeyCode::Char('k'), KeyModifiers::CONTROL);
+        assert_eq!(keymap.action(&old, false, None), None);
+
+        let keymap = Keymap::with_overrides(&overrides("mark", "space"), &[]).unwrap();
+        let event = KeyEvent::new(KeyCode::Char(' '), KeyModifiers::NONE);
+        assert_eq!(keymap.action(&event, false, None), Some(Action::Mark));
+
+        assert!(Keymap::with_overrides(&overrides("mark", "m"), &[]).is_err());
+        assert!(Keymap::with_overrides(&overrides("explode", "ctrl+z"), &[]).is_err());
+    }
+}
@@ -1,34 +1,116 @@
 mod app;
 mod args;
+mod cli;
+mod config;
 mod interface;
+mod keymap;
+mod matcher;
+#[cfg(target_os = "linux")]
+mod pidfd;
+#[cfg(target_os = "linux")]
+mod procfs;
+mod query;
+mod signal;
+mod theme;
+mod tree;
 mod ui;
 
 use ratatui::crossterm::{event::{DisableMouseCapture, EnableMouseCapture}, execute, terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen}};
 use ratatui::backend::CrosstermBackend;
 use ratatui::Terminal;
-use std::io::stdout;
+use std::io::{stdout, Write};
 use std::error::Error;
+#[cfg(unix)]
+use std::fs::File;
 use scopeguard::defer;
 
-use app::App;
+use app::{App, Startup};
+use args::{Args, Command};
+use clap::{CommandFactory, FromArgMatches};
+use config::Config;
 
 fn main() -> Result<(), Box<dyn Error>> {
-    let mut app = App::new();
+    let matches = Args::command().get_matches();
+    let mut args = Args::from_arg_matches(&matches).unwrap_or_else(|err| err.exit());
 
-    let mut stdout = stdout();
-    enable_raw_mode()?;
+    // Flags given on the command line win over the config file
+    if let Err(err) = Config::load(args.config.as_deref()).and_then(|config| config.apply(&mut args, &matches)) {
+        eprintln!("{}", err);
+        std::process::exit(1);
+    }
 
-    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
+    if let Some(command) = &args.command {
+        let res = match command {
+            Command::List(list) => cli::list(&args, list).map(|()| 0),
+            Command::Kill(kill) => cli::kill(&args, kill),
+        };
 
-    let backend = CrosstermBackend::new(stdout);
-    let mut terminal = Terminal::new(backend)?;
+        match res {
+            Ok(0) => return Ok(()),
+            Ok(code) => std::process::exit(code),
+            Err(err) => {
+                eprintln!("{}", err);
+                std::process::exit(cli::EXIT_ERROR);
+            }
+        }
+    }
 
-    let res = app.run(&mut terminal);
+    let mut app = match App::new(args) {
+        Ok(app) => app,
+        Err(err) => {
+            eprintln!("{}", err);
+            std::process::exit(1);
+        }
+    };
+
+    let startup = app.settle_initial_query();
+    let res = match startup {
+        Startup::Open => run_interface(&mut app),
+        Startup::Exit(_) => Ok(()),
+    };
+    app.stop();
+    let picking = app.is_picking();
+    let picked = app.take_picked();
 
     defer!(
         app.join_threads();
     );
 
+    if let Err(err) = res {
+        eprintln!("{}", err);
+    }
+
+    // Nothing matched with --exit-0, or --select-1 couldn't kill the match
+    if let Startup::Exit(code @ 1..) = startup {
+        std::process::exit(code);
+    }
+
+    if picking {
+        // Nothing picked means the picker was cancelled, like fzf
+        if picked.is_empty() {
+            std::process::exit(130);
+        }
+
+        let mut out = stdout().lock();
+        for line in picked {
+            writeln!(out, "{}", line)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn run_interface(app: &mut App) -> Result<(), Box<dyn Error>> {
+    let mut tty = open_tty();
+    enable_raw_mode()?;
+
+    execute!(tty, EnterAlternateScreen, EnableMouseCapture)?;
+
+    let backend = CrosstermBackend::new(tty);
+    let mut terminal = Terminal::new(backend)?;
+
+    let res = app.run(&mut terminal);
+
     // Restore terminal
     disable_raw_mode()?;
     execute!(
@@ -38,9 +120,20 @@ fn main() -> Result<(), Box<dyn Error>> {
     )?;
     terminal.show_cursor()?;
 
-    if let Err(err) = res {
-        eprintln!("{}", err.to_string());
+    res
+}
+
+// Draw on the terminal itself rather than stdout, so stdout can be piped
+// somewhere while picking
+#[cfg(unix)]
+fn open_tty() -> Box<dyn Write> {
+    match File::options().read(true).write(true).open("/dev/tty") {
+        Ok(tty) => Box::new(tty),
+        Err(_) => Box::new(stdout()),
     }
+}
 
-    Ok(())
+#[cfg(not(unix))]
+fn open_tty() -> Box<dyn Write> {
+    Box::new(stdout())
 }
@@ -0,0 +1,375 @@
+use clap::ValueEnum;
+use regex::{Regex, RegexBuilder};
+use rust_fuzzy_search::fuzzy_compare;
+
+// How the free text in a search is compared with processes
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum SearchMode {
+    Fuzzy,
+    // The whole text has to be the search
+    Exact,
+    Prefix,
+    Substring,
+    Regex,
+}
+
+// The fuzzy matcher used in the fuzzy search mode
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Algorithm {
+    // Every character of the search has to appear in order, scored like fzf
+    // with bonuses for landing on word boundaries and for runs of characters
+    Fzf,
+    // Trigram similarity, anything scoring below the threshold is dropped
+    Trigram,
+}
+
+const SCORE_MATCH: i32 = 16;
+const SCORE_GAP_START: i32 = -3;
+const SCORE_GAP_EXTENSION: i32 = -1;
+
+// Matching the first character of a word is worth about as much as the gap
+// it takes to get there
+const BONUS_BOUNDARY: i32 = SCORE_MATCH / 2;
+const BONUS_BOUNDARY_WHITE: i32 = BONUS_BOUNDARY + 2;
+const BONUS_BOUNDARY_DELIMITER: i32 = BONUS_BOUNDARY + 1;
+const BONUS_NON_WORD: i32 = SCORE_MATCH / 2;
+const BONUS_CAMEL: i32 = BONUS_BOUNDARY + SCORE_GAP_EXTENSION;
+// A run never earns less than a gap would have cost
+const BONUS_CONSECUTIVE: i32 = -(SCORE_GAP_START + SCORE_GAP_EXTENSION);
+// The first search character counts double, so "ng" prefers "nginx" over "mongo"
+const BONUS_FIRST_CHAR_MULTIPLIER: i32 = 2;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum CharClass {
+    White,
+    Delimiter,
+    NonWord,
+    Lower,
+    Upper,
+    Number,
+}
+
+impl CharClass {
+    fn of(c: char) -> Self {
+        if c.is_whitespace() {
+            Self::White
+        } else if matches!(c, '/' | ',' | ':' | ';' | '|' | '=') {
+            Self::Delimiter
+        } else if c.is_lowercase() {
+            Self::Lower
+        } else if c.is_uppercase() {
+            Self::Upper
+        } else if c.is_numeric() {
+            Self::Number
+        } else if c.is_alphabetic() {
+            Self::Lower
+        } else {
+            Self::NonWord
+        }
+    }
+
+    fn is_word(self) -> bool {
+        matches!(self, Self::Lower | Self::Upper | Self::Number)
+    }
+}
+
+// The bonus for matching a character of class current right after one of class prev
+fn bonus(prev: CharClass, current: CharClass) -> i32 {
+    if current.is_word() {
+        match prev {
+            CharClass::White => BONUS_BOUNDARY_WHITE,
+            CharClass::Delimiter => BONUS_BOUNDARY_DELIMITER,
+            CharClass::NonWord => BONUS_BOUNDARY,
+            CharClass::Lower if current == CharClass::Upper => BONUS_CAMEL,
+            CharClass::Lower | CharClass::Upper if current == CharClass::Number => BONUS_CAMEL,
+            _ => 0,
+        }
+    } else if current == CharClass::White {
+        BONUS_BOUNDARY_WHITE
+    } else {
+        BONUS_NON_WORD
+    }
+}
+
+impl SearchMode {
+    pub const ALL: [SearchMode; 5] = [Self::Fuzzy, Self::Exact, Self::Prefix, Self::Substring, Self::Regex];
+
+    pub fn next(self) -> Self {
+        let i = Self::ALL
+            .iter()
+            .position(|&mode| mode == self)
+            .unwrap_or(0);
+        Self::ALL[(i + 1) % Self::ALL.len()]
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Fuzzy => "fuzzy",
+            Self::Exact => "exact",
+            Self::Prefix => "prefix",
+            Self::Substring => "substring",
+            Self::Regex => "regex",
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct MatchOptions {
+    pub mode: SearchMode,
+    pub algorithm: Algorithm,
+    // Only used by the trigram algorithm
+    pub threshold: f32,
+    // Otherwise the case is ignored unless the search has an uppercase
+    // letter in it, like smart case in fzf and vim
+    pub case_sensitive: bool,
+}
+
+// The same as the command line defaults
+impl Default for MatchOptions {
+    fn default() -> Self {
+        Self {
+            mode: SearchMode::Fuzzy,
+            algorithm: Algorithm::Fzf,
+            threshold: 0.3,
+            case_sensitive: false,
+        }
+    }
+}
+
+// Where a pattern matched, positions are the char indices in the text it
+// landed on. Higher scores are better matches
+pub struct Match {
+    pub score: f32,
+    pub positions: Vec<usize>,
+}
+
+// A search ready to be matched against any number of texts
+#[derive(Clone)]
+pub struct Pattern {
+    kind: PatternKind,
+}
+
+#[derive(Clone)]
+enum PatternKind {
+    Fzf(Vec<char>, bool),
+    Trigram(String, f32),
+    // The mode, the search split into chars and whether case matters
+    Literal(SearchMode, Vec<char>, bool),
+    Regex(Regex),
+}
+
+impl Pattern {
+    pub fn new(search: &str, options: &MatchOptions) -> Result<Self, String> {
+        let case_sensitive = options.case_sensitive || search.chars().any(char::is_uppercase);
+
+        let kind = match options.mode {
+            SearchMode::Fuzzy => match options.algorithm {
+                Algorithm::Fzf => PatternKind::Fzf(fold_all(search, case_sensitive), case_sensitive),
+                Algorithm::Trigram => PatternKind::Trigram(search.to_string(), options.threshold),
+            },
+            SearchMode::Regex => PatternKind::Regex(
+                RegexBuilder::new(search)
+                    .case_insensitive(!case_sensitive)
+                    .build()
+                    .map_err(|_| format!("invalid regex '{}'", search))?
+            ),
+            mode => PatternKind::Literal(mode, fold_all(search, case_sensitive), case_sensitive),
+        };
+
+        Ok(Self { kind })
+    }
+
+    // Matches text exactly as written, for when the text came from a process
+    // rather than from the user
+    pub fn exact(text: &str) -> Self {
+        Self { kind: PatternKind::Literal(SearchMode::Exact, text.chars().collect(), true) }
+    }
+
+    pub fn find(&self, text: &str) -> Option<Match> {
+        match &self.kind {
+            PatternKind::Fzf(search, case_sensitive) => subsequence(search, text, *case_sensitive),
+            PatternKind::Trigram(search, threshold) => {
+                // A short search scores poorly against a long text like a
+                // command line, so also try each word on its own
+                let score = text
+                    .split_ascii_whitespace()
+                    .map(|word| fuzzy_compare(search, word))
+                    .fold(fuzzy_compare(search, text), f32::max);
+                (score >= *threshold).then_some(Match { score, positions: Vec::new() })
+            },
+            PatternKind::Literal(mode, search, case_sensitive) => {
+                let text = fold_all(text, *case_sensitive);
+                let start = match mode {
+                    SearchMode::Exact => (text == *search).then_some(0),
+                    SearchMode::Prefix => text.starts_with(search).then_some(0),
+                    _ if search.is_empty() => Some(0),
+                    _ => text.windows(search.len()).position(|window| window == search.as_slice()),
+                }?;
+                Some(Match {
+                    // Earlier matches in shorter texts first
+                    score: -(start as f32) - text.len() as f32 / 1000.0,
+                    positions: (start..start + search.len()).collect(),
+                })
+            },
+            PatternKind::Regex(re) => {
+                let found = re.find(text)?;
+                let start = text[..found.start()].chars().count();
+                let len = found.as_str().chars().count();
+                Some(Match {
+                    score: -(start as f32),
+                    positions: (start..start + len).collect(),
+                })
+            },
+        }
+    }
+}
+
+fn fold_all(text: &str, case_sensitive: bool) -> Vec<char> {
+    text.chars()
+        .map(|c| if case_sensitive { c } else { fold_case(c) })
+        .collect()
+}
+
+fn fold_case(c: char) -> char {
+    c.to_lowercase().next().unwrap_or(c)
+}
+
+// Finds the best scoring way to match pattern as a subsequence of text, or
+// None if text doesn't contain every character of pattern in order. The
+// pattern is already folded if case doesn't matter
+fn subsequence(pattern: &[char], text: &str, case_sensitive: bool) -> Option<Match> {
+    let chars = text.chars().collect::<Vec<char>>();
+    let folded = fold_all(text, case_sensitive);
+
+    if pattern.is_empty() {
+        return Some(Match { score: 0.0, positions: Vec::new() });
+    }
+
+    // Cheap check before the full table, most processes don't match at all
+    let mut remaining = pattern.iter().peekable();
+    folded.iter().for_each(|c| {
+        if remaining.peek() == Some(&c) {
+            remaining.next();
+        }
+    });
+    if remaining.peek().is_some() {
+        return None;
+    }
+
+    // Only the span from the first place the search can start to the last
+    // place it can end needs a table, which keeps long command lines cheap
+    let start = folded.iter().position(|&c| c == pattern[0])?;
+    let end = folded.iter().rposition(|&c| c == pattern[pattern.len() - 1])?;
+    let folded = &folded[start..=end];
+
+    let bonuses = (start..=end)
+        .map(|j| {
+            // The start of the text counts as coming after whitespace
+            let prev = if j == 0 { CharClass::White } else { CharClass::of(chars[j - 1]) };
+            bonus(prev, CharClass::of(chars[j]))
+        })
+        .collect::<Vec<i32>>();
+
+    // scores[i][j] is the best score with search character i on text
+    // character j, runs[i][j] the bonus a run continuing from there keeps
+    // and from[i][j] where search character i - 1 landed on that path, all
+    // counted from start
+    let n = folded.len();
+    let mut scores = vec![vec![None::<i32>; n]; pattern.len()];
+    let mut runs = vec![vec![0; n]; pattern.len()];
+    let mut from = vec![vec![0; n]; pattern.len()];
+
+    for (i, &p) in pattern.iter().enumerate() {
+        // Best score of the previous row reachable through a gap, and where it was
+        let mut gapped: Option<(i32, usize)> = None;
+
+        for j in i..n {
+            if i > 0 && j >= 2 {
+                gapped = gapped.map(|(score, k)| (score + SCORE_GAP_EXTENSION, k));
+                if let Some(score) = scores[i - 1][j - 2] {
+                    let opened = score + SCORE_GAP_START;
+                    if gapped.is_none_or(|(best, _)| opened > best) {
+                        gapped = Some((opened, j - 2));
+                    }
+                }
+            }
+
+            if folded[j] != p {
+                continue;
+            }
+
+            if i == 0 {
+                scores[i][j] = Some(SCORE_MATCH + bonuses[j] * BONUS_FIRST_CHAR_MULTIPLIER);
+                runs[i][j] = bonuses[j].max(BONUS_CONSECUTIVE);
+                continue;
+            }
+
+            let consecutive = scores[i - 1][j - 1].map(|score| {
+                let run = runs[i - 1][j - 1].max(bonuses[j]);
+                (score + SCORE_MATCH + run, run, j - 1)
+            });
+            let after_gap = gapped.map(|(score, k)| {
+                (score + SCORE_MATCH + bonuses[j], bonuses[j].max(BONUS_CONSECUTIVE), k)
+            });
+
+            let best = match (consecutive, after_gap) {
+                (Some(c), Some(g)) => Some(if g.0 > c.0 { g } else { c }),
+                (c, g) => c.or(g),
+            };
+            if let Some((score, run, k)) = best {
+                scores[i][j] = Some(score);
+                runs[i][j] = run;
+                from[i][j] = k;
+            }
+        }
+    }
+
+    let last = pattern.len() - 1;
+    let (score, end) = (0..n)
+        .filter_map(|j| scores[last][j].map(|score| (score, j)))
+        // The earliest of equally good matches
+        .max_by(|a, b| a.0.cmp(&b.0).then(b.1.cmp(&a.1)))?;
+
+    let mut positions = vec![0; pattern.len()];
+    let mut j = end;
+    for i in (0..pattern.len()).rev() {
+        positions[i] = start + j;
+        j = from[i][j];
+    }
+
+    Some(Match { score: score as f32, positions })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn score(pattern: &str, text: &str) -> Option<f32> {
+        subsequence(&fold_all(pattern, false), text, false).map(|m| m.score)
+    }
+
+    #[test]
+    fn subsequence_needs_every_char_in_order() {
+        let found = subsequence(&fold_all("ngx", false), "nginx", false).unwrap();
+        assert_eq!(found.positions, [0, 1, 4]);
+        assert!(score("xgn", "nginx").is_none());
+        assert!(score("NGINX", "nginx").is_some());
+        assert!(subsequence(&fold_all("NGINX", true), "nginx", true).is_none());
+        assert_eq!(score("", "nginx"), Some(0.0));
+    }
+
+    #[test]
+    fn subsequence_only_scores_the_span_that_can_match() {
+        let text = format!("java -cp {} /usr/sbin/nginx -g", "x".repeat(10_000));
+        let found = subsequence(&fold_all("ngi", false), &text, false).unwrap();
+        assert_eq!(found.positions, [10_020, 10_021, 10_022]);
+        assert_eq!(Some(found.score), score("ngi", "/nginx"));
+    }
+
+    #[test]
+    fn subsequence_prefers_word_starts_and_runs() {
+        assert!(score("ng", "nginx") > score("ng", "mongo"));
+        assert!(score("ps", "postgres") < score("ps", "php-server"));
+        assert!(score("node", "node") > score("node", "n-o-d-e"));
+    }
+}
@@ -0,0 +1,55 @@
+use std::{io, os::fd::{AsRawFd, FromRawFd, OwnedFd}, ptr};
+
+// A handle to one specific process. Unlike a plain pid it can't end up
+// pointing at a different process if the pid is recycled, so a signal sent
+// through it only ever reaches the process it was opened for.
+pub struct PidFd {
+    fd: OwnedFd,
+}
+
+impl PidFd {
+    pub fn open(pid: u64) -> io::Result<Self> {
+        let pid = libc::pid_t::try_from(pid)
+            .map_err(|_| io::Error::from(io::ErrorKind::InvalidInput))?;
+
+        // SAFETY: pidfd_open takes a pid and flags and returns a new fd or -1
+        let fd = unsafe { libc::syscall(libc::SYS_pidfd_open, pid, 0) };
+        if fd < 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        // SAFETY: the fd was just returned by the kernel and nothing else owns it
+        Ok(Self { fd: unsafe { OwnedFd::from_raw_fd(fd as i32) } })
+    }
+
+    pub fn send_signal(&self, signal: i32) -> io::Result<()> {
+        // SAFETY: the fd is a valid pidfd for as long as self is alive
+        let res = unsafe {
+            libc::syscall(
+                libc::SYS_pidfd_send_signal,
+                self.fd.as_raw_fd(),
+                signal,
+                ptr::null::<libc::siginfo_t>(),
+                0
+            )
+        };
+        if res < 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        Ok(())
+    }
+}
+
+// Plain kill(2), for kernels older than 5.3 that don't have pidfds
+pub fn kill(pid: u64, signal: i32) -> io::Result<()> {
+    let pid = libc::pid_t::try_from(pid)
+        .map_err(|_| io::Error::from(io::ErrorKind::InvalidInput))?;
+
+    // SAFETY: kill has no memory safety preconditions
+    if unsafe { libc::kill(pid, signal) } < 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    Ok(())
+}
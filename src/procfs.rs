@@ -0,0 +1,269 @@
+use std::{collections::HashMap, fs, io, path::PathBuf};
+
+// Raw readers for the files under /proc that the monitor needs. Everything in
+// here returns an io::Result since a process can exit between listing /proc
+// and reading its files.
+
+pub struct Stat {
+    pub comm: String,
+    pub state: char,
+    pub ppid: u64,
+    pub utime: u64,
+    pub stime: u64,
+    pub priority: i64,
+    pub nice: i64,
+    pub num_threads: u64,
+    pub starttime: u64,
+}
+
+// Both in pages
+pub struct Statm {
+    pub size: u64,
+    pub resident: u64,
+}
+
+pub struct Status {
+    pub uid: u32,
+    pub gid: u32,
+}
+
+pub struct CpuTimes {
+    pub total: u64,
+    pub num_cpus: usize,
+}
+
+pub fn list_pids() -> io::Result<Vec<u64>> {
+    Ok(fs::read_dir("/proc")?
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| entry.file_name().to_str()?.parse::<u64>().ok())
+        .collect())
+}
+
+pub fn read_stat(pid: u64) -> io::Result<Stat> {
+    let contents = fs::read_to_string(pid_path(pid, "stat"))?;
+    parse_stat(&contents).ok_or_else(|| invalid_data("stat"))
+}
+
+pub fn read_statm(pid: u64) -> io::Result<Statm> {
+    let contents = fs::read_to_string(pid_path(pid, "statm"))?;
+    let mut cols = contents
+        .split_ascii_whitespace()
+        .map(|col| col.parse::<u64>().ok());
+    let (Some(Some(size)), Some(Some(resident))) = (cols.next(), cols.next()) else {
+        return Err(invalid_data("statm"));
+    };
+
+    Ok(Statm { size, resident })
+}
+
+pub fn read_status(pid: u64) -> io::Result<Status> {
+    let contents = fs::read_to_string(pid_path(pid, "status"))?;
+
+    // The real ids are the first of the four on the Uid and Gid lines
+    let real_id = |prefix: &str| {
+        contents
+            .lines()
+            .find_map(|line| line.strip_prefix(prefix))
+            .and_then(|rest| rest.split_ascii_whitespace().next())
+            .and_then(|col| col.parse::<u32>().ok())
+            .ok_or_else(|| invalid_data("status"))
+    };
+
+    Ok(Status { uid: real_id("Uid:")?, gid: real_id("Gid:")? })
+}
+
+// The arguments the process was started with. Empty for kernel threads and
+// zombies, which have no command line
+pub fn read_cmdline(pid: u64) -> io::Result<Vec<String>> {
+    read_nul_separated(pid, "cmdline")
+}
+
+// Reads a file of nul separated strings
+fn read_nul_separated(pid: u64, file: &str) -> io::Result<Vec<String>> {
+    let contents = fs::read(pid_path(pid, file))?;
+
+    Ok(contents
+        .split(|&b| b == 0)
+        .filter(|arg| !arg.is_empty())
+        .map(|arg| String::from_utf8_lossy(arg).into_owned())
+        .collect())
+}
+
+// Path of the executable, only readable for our own processes unless root
+pub fn read_exe(pid: u64) -> io::Result<PathBuf> {
+    fs::read_link(pid_path(pid, "exe"))
+}
+
+// Like the command line, these need the same user as the process or root
+pub fn read_environ(pid: u64) -> io::Result<Vec<String>> {
+    read_nul_separated(pid, "environ")
+}
+
+pub fn read_cwd(pid: u64) -> io::Result<PathBuf> {
+    fs::read_link(pid_path(pid, "cwd"))
+}
+
+pub fn count_open_files(pid: u64) -> io::Result<usize> {
+    Ok(fs::read_dir(pid_path(pid, "fd"))?.count())
+}
+
+// The cgroup paths without their hierarchy ids, a single one on cgroup v2.
+// v1 lists every controller, which mostly share the same few paths
+pub fn read_cgroups(pid: u64) -> io::Result<Vec<String>> {
+    let contents = fs::read_to_string(pid_path(pid, "cgroup"))?;
+
+    let mut paths: Vec<String> = Vec::new();
+    contents
+        .lines()
+        .filter_map(|line| line.splitn(3, ':').nth(2))
+        .for_each(|path| {
+            if !paths.iter().any(|seen| seen == path) {
+                paths.push(path.to_string());
+            }
+        });
+    Ok(paths)
+}
+
+// Map of uid to user name from /etc/passwd
+pub fn read_user_names() -> HashMap<u32, String> {
+    read_id_names("/etc/passwd")
+}
+
+// Map of gid to group name from /etc/group
+pub fn read_group_names() -> HashMap<u32, String> {
+    read_id_names("/etc/group")
+}
+
+// Both files have the name first and the id third
+fn read_id_names(path: &str) -> HashMap<u32, String> {
+    fs::read_to_string(path)
+        .unwrap_or_default()
+        .lines()
+        .filter_map(|line| {
+            let mut cols = line.split(':');
+            let name = cols.next()?;
+            let id = cols.nth(1)?.parse::<u32>().ok()?;
+            Some((id, name.to_string()))
+        })
+        .collect()
+}
+
+// Total physical memory in bytes from /proc/meminfo
+pub fn read_mem_total() -> io::Result<u64> {
+    let contents = fs::read_to_string("/proc/meminfo")?;
+    contents
+        .lines()
+        .find_map(|line| {
+            let rest = line.strip_prefix("MemTotal:")?;
+            let kib = rest.split_ascii_whitespace().next()?.parse::<u64>().ok()?;
+            Some(kib * 1024)
+        })
+        .ok_or_else(|| invalid_data("meminfo"))
+}
+
+// Jiffies spent by all cpus combined and the number of cpus from /proc/stat
+pub fn read_cpu_times() -> io::Result<CpuTimes> {
+    let contents = fs::read_to_string("/proc/stat")?;
+    let mut lines = contents.lines();
+
+    // guest and guest_nice are already counted in user and nice
+    let total = lines
+        .next()
+        .and_then(|line| line.strip_prefix("cpu "))
+        .map(|rest| {
+            rest.split_ascii_whitespace()
+                .take(8)
+                .filter_map(|col| col.parse::<u64>().ok())
+                .sum::<u64>()
+        })
+        .ok_or_else(|| invalid_data("stat"))?;
+    let num_cpus = lines
+        .take_while(|line| line.starts_with("cpu"))
+        .count()
+        .max(1);
+
+    Ok(CpuTimes { total, num_cpus })
+}
+
+// Seconds since boot from /proc/uptime
+pub fn read_uptime() -> io::Result<f64> {
+    let contents = fs::read_to_string("/proc/uptime")?;
+    contents
+        .split_ascii_whitespace()
+        .next()
+        .and_then(|col| col.parse::<f64>().ok())
+        .ok_or_else(|| invalid_data("uptime"))
+}
+
+pub fn clock_ticks() -> u64 {
+    // SAFETY: sysconf has no preconditions
+    let ticks = unsafe { libc::sysconf(libc::_SC_CLK_TCK) };
+    if ticks > 0 { ticks as u64 } else { 100 }
+}
+
+pub fn page_size() -> u64 {
+    // SAFETY: sysconf has no preconditions
+    let size = unsafe { libc::sysconf(libc::_SC_PAGESIZE) };
+    if size > 0 { size as u64 } else { 4096 }
+}
+
+fn parse_stat(contents: &str) -> Option<Stat> {
+    // The command name is wrapped in parentheses and may itself contain spaces
+    // or parentheses, so split around the last ')' rather than on whitespace
+    let open = contents.find('(')?;
+    let close = contents.rfind(')')?;
+    let comm = contents.get(open + 1..close)?.to_string();
+
+    // Fields after the command, starting with the state (field 3 in proc(5))
+    let fields = contents
+        .get(close + 1..)?
+        .split_ascii_whitespace()
+        .collect::<Vec<&str>>();
+    let field = |n: usize| fields.get(n - 3)?.parse::<u64>().ok();
+    let signed = |n: usize| fields.get(n - 3)?.parse::<i64>().ok();
+
+    Some(Stat {
+        comm,
+        state: fields.first()?.chars().next()?,
+        ppid: field(4)?,
+        utime: field(14)?,
+        stime: field(15)?,
+        priority: signed(18)?,
+        nice: signed(19)?,
+        num_threads: field(20)?,
+        starttime: field(22)?,
+    })
+}
+
+fn pid_path(pid: u64, file: &str) -> PathBuf {
+    PathBuf::from(format!("/proc/{}/{}", pid, file))
+}
+
+fn invalid_data(file: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("malformed /proc {} entry", file))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stat_with_odd_command_name() {
+        let contents = "4242 (my (weird) cmd) S 1 4242 4242 0 -1 4194560 100 0 0 0 \
+            12 34 0 0 20 -5 3 0 98765 1000000 200 18446744073709551615\n";
+        let stat = parse_stat(contents).unwrap();
+        assert_eq!(stat.comm, "my (weird) cmd");
+        assert_eq!(stat.state, 'S');
+        assert_eq!(stat.ppid, 1);
+        assert_eq!((stat.utime, stat.stime), (12, 34));
+        assert_eq!((stat.priority, stat.nice), (20, -5));
+        assert_eq!(stat.num_threads, 3);
+        assert_eq!(stat.starttime, 98765);
+    }
+
+    #[test]
+    fn truncated_stat() {
+        assert!(parse_stat("4242 (bash) S 1 4242").is_none());
+        assert!(parse_stat("4242 bash S 1").is_none());
+    }
+}
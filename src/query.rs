@@ -0,0 +1,330 @@
+use regex::{Regex, RegexBuilder};
+
+use crate::interface::{Process, SearchField};
+use crate::matcher::{MatchOptions, Pattern};
+
+// A parsed search box. Words without a field are matched with the current
+// search mode, field filters narrow the list down before that:
+//
+//     user:postgres cpu>20 mem>5 pid:1200..1300 state:Z name:~^node !chrome
+//
+// On text fields ':' matches part of the value, '=' the whole value and ':~' a
+// regex, all ignoring case. Numeric fields take ':' with a number or a range
+// with either end left open, or one of = < <= > >=. Any term can be negated
+// with a leading '!'.
+pub struct Query {
+    text: String,
+    // None if the query is only filters
+    pattern: Option<Pattern>,
+    field: SearchField,
+    filters: Vec<Filter>,
+}
+
+struct Filter {
+    negated: bool,
+    test: Test,
+}
+
+enum Test {
+    // Free text, only ends up here when negated since it can't narrow
+    // anything down otherwise
+    Text(Pattern, SearchField),
+    Contains(Field, String),
+    Equals(Field, String),
+    Regex(Field, Regex),
+    Compare(Field, Op, f64),
+    // Inclusive on both ends
+    Range(Field, f64, f64),
+}
+
+#[derive(Clone, Copy)]
+enum Op {
+    Eq,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+#[derive(Clone, Copy)]
+enum Field {
+    Name,
+    Cmd,
+    User,
+    State,
+    Pid,
+    Ppid,
+    Mem,
+    #[cfg(any(target_os = "linux", target_os = "macos"))]
+    Cpu,
+}
+
+impl Query {
+    // Text is matched against command lines instead of commands if
+    // search_cmdline is set
+    pub fn parse(input: &str, options: &MatchOptions, search_cmdline: bool) -> Result<Self, String> {
+        let mut text = Vec::new();
+        let mut filters = Vec::new();
+
+        for term in input.split_ascii_whitespace() {
+            let (negated, term) = match term.strip_prefix('!') {
+                Some(rest) => (true, rest),
+                None => (false, term),
+            };
+            if term.is_empty() {
+                return Err(String::from("nothing after '!'"));
+            }
+
+            match Self::parse_filter(term)? {
+                Some(test) => filters.push(Filter { negated, test }),
+                None if negated => filters.push(Filter {
+                    negated,
+                    test: Test::Text(
+                        Pattern::new(term, options)?,
+                        SearchField::detect(term, search_cmdline)
+                    ),
+                }),
+                None => text.push(term),
+            }
+        }
+
+        let text = text.join(" ");
+        let pattern = if text.is_empty() {
+            None
+        } else {
+            Some(Pattern::new(&text, options)?)
+        };
+
+        Ok(Self {
+            field: SearchField::detect(&text, search_cmdline),
+            text,
+            pattern,
+            filters,
+        })
+    }
+
+    // The free text left over once the filters are taken out, empty if the
+    // query is only filters
+    pub fn get_text(&self) -> &str {
+        &self.text
+    }
+
+    // Whether proc passes every filter
+    pub fn matches(&self, proc: &Process) -> bool {
+        self.filters
+            .iter()
+            .all(|filter| filter.test.matches(proc) != filter.negated)
+    }
+
+    // How well proc matches the free text, None if it doesn't
+    pub fn score(&self, proc: &Process) -> Option<f32> {
+        match &self.pattern {
+            Some(pattern) => pattern.find(&self.field.text(proc)).map(|m| m.score),
+            None => Some(0.0),
+        }
+    }
+
+    // The chars of field that the free text landed on, for highlighting
+    pub fn positions(&self, proc: &Process, field: SearchField) -> Vec<usize> {
+        self.pattern
+            .as_ref()
+            .filter(|_| field == self.field)
+            .and_then(|pattern| pattern.find(&field.text(proc)))
+            .map(|m| m.positions)
+            .unwrap_or_default()
+    }
+
+    // None if the term is plain text rather than a filter
+    fn parse_filter(term: &str) -> Result<Option<Test>, String> {
+        let Some(split) = term.find([':', '<', '>', '=']) else {
+            return Ok(None);
+        };
+        let (name, rest) = term.split_at(split);
+        if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphabetic()) {
+            return Ok(None);
+        }
+        // Text like "nginx: worker" or "mode=prod" is searched for as it is
+        let Some(field) = Field::parse(name)? else {
+            return Ok(None);
+        };
+
+        let (op, value) = [":~", ">=", "<=", ":", "=", "<", ">"]
+            .iter()
+            .find_map(|&op| rest.strip_prefix(op).map(|value| (op, value)))
+            .unwrap_or(("", rest));
+        if value.is_empty() {
+            return Err(format!("missing value after '{}{}'", name, op));
+        }
+
+        if op == ":~" {
+            return RegexBuilder::new(value)
+                .case_insensitive(true)
+                .build()
+                .map(|re| Some(Test::Regex(field, re)))
+                .map_err(|_| format!("invalid regex '{}'", value));
+        }
+
+        if !field.is_numeric() {
+            return match op {
+                ":" => Ok(Some(Test::Contains(field, value.to_lowercase()))),
+                "=" => Ok(Some(Test::Equals(field, value.to_lowercase()))),
+                _ => Err(format!("'{}' can't be compared with '{}'", name, op)),
+            };
+        }
+
+        if op == ":" {
+            if let Some((low, high)) = value.split_once("..") {
+                let low = if low.is_empty() { f64::MIN } else { Self::parse_number(low)? };
+                let high = if high.is_empty() { f64::MAX } else { Self::parse_number(high)? };
+                return Ok(Some(Test::Range(field, low, high)));
+            }
+        }
+
+        let op = match op {
+            ":" | "=" => Op::Eq,
+            "<" => Op::Lt,
+            "<=" => Op::Le,
+            ">" => Op::Gt,
+            _ => Op::Ge,
+        };
+        Ok(Some(Test::Compare(field, op, Self::parse_number(value)?)))
+    }
+
+    fn parse_number(value: &str) -> Result<f64, String> {
+        value
+            .parse::<f64>()
+            .map_err(|_| format!("'{}' is not a number", value))
+    }
+}
+
+impl Test {
+    fn matches(&self, proc: &Process) -> bool {
+        match self {
+            Self::Text(pattern, field) => pattern.find(&field.text(proc)).is_some(),
+            Self::Contains(field, value) => field.text(proc).to_lowercase().contains(value.as_str()),
+            Self::Equals(field, value) => field.text(proc).to_lowercase() == *value,
+            Self::Regex(field, re) => re.is_match(&field.text(proc)),
+            Self::Compare(field, op, value) => {
+                let number = field.number(proc);
+                match op {
+                    Op::Eq => number == *value,
+                    Op::Lt => number < *value,
+                    Op::Le => number <= *value,
+                    Op::Gt => number > *value,
+                    Op::Ge => number >= *value,
+                }
+            },
+            Self::Range(field, low, high) => (*low..=*high).contains(&field.number(proc)),
+        }
+    }
+}
+
+impl Field {
+    // None if name isn't a field at all
+    fn parse(name: &str) -> Result<Option<Self>, String> {
+        let field = match name.to_ascii_lowercase().as_str() {
+            "name" => Self::Name,
+            "cmd" => Self::Cmd,
+            "user" => Self::User,
+            "state" => Self::State,
+            "pid" => Self::Pid,
+            "ppid" => Self::Ppid,
+            "mem" => Self::Mem,
+            #[cfg(any(target_os = "linux", target_os = "macos"))]
+            "cpu" => Self::Cpu,
+            #[cfg(target_os = "windows")]
+            "cpu" => return Err(String::from("cpu usage isn't available on windows")),
+            _ => return Ok(None),
+        };
+        Ok(Some(field))
+    }
+
+    fn is_numeric(self) -> bool {
+        !matches!(self, Self::Name | Self::Cmd | Self::User | Self::State)
+    }
+
+    fn text(self, proc: &Process) -> String {
+        match self {
+            Self::Name => proc.get_command().to_string(),
+            Self::Cmd => proc.get_full_command().to_string(),
+            Self::User => proc.get_user().to_string(),
+            Self::State => proc.get_state().to_string(),
+            Self::Pid => proc.get_pid().to_string(),
+            Self::Ppid => proc.get_ppid().to_string(),
+            #[cfg(target_os = "windows")]
+            Self::Mem => proc.get_mem().to_string(),
+            // As shown in the list
+            #[cfg(any(target_os = "linux", target_os = "macos"))]
+            Self::Mem => format!("{:.1}", proc.get_mem()),
+            #[cfg(any(target_os = "linux", target_os = "macos"))]
+            Self::Cpu => format!("{:.1}", proc.get_cpu()),
+        }
+    }
+
+    fn number(self, proc: &Process) -> f64 {
+        match self {
+            Self::Pid => proc.get_pid() as f64,
+            Self::Ppid => proc.get_ppid() as f64,
+            // In KiB since windows has no percentage to go by
+            #[cfg(target_os = "windows")]
+            Self::Mem => proc.get_mem_kib() as f64,
+            #[cfg(any(target_os = "linux", target_os = "macos"))]
+            Self::Mem => proc.get_displayed_mem(),
+            #[cfg(any(target_os = "linux", target_os = "macos"))]
+            Self::Cpu => proc.get_displayed_cpu(),
+            _ => f64::NAN,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interface::{FixtureSource, ProcessSource};
+
+    fn matching(input: &str, search_cmdline: bool) -> Vec<u64> {
+        let query = Query::parse(input, &MatchOptions::default(), search_cmdline).unwrap();
+        FixtureSource::parse(include_str!("../fixtures/sample.tsv"))
+            .unwrap()
+            .enumerate()
+            .unwrap()
+            .procs
+            .iter()
+            .filter(|proc| query.matches(proc) && query.score(proc).is_some())
+            .map(|proc| proc.get_pid())
+            .collect()
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "macos"))]
+    #[test]
+    fn numbers_compare_as_displayed() {
+        assert!(matching("mem=0.3", false).contains(&1));
+        assert!(matching("mem:0.1..0.3", false).contains(&1));
+        assert!(!matching("mem<0.3", false).contains(&1));
+    }
+
+    #[test]
+    fn unknown_fields_are_text() {
+        assert!(matching("nginx: worker", true).contains(&2001));
+        assert!(matching("mode=prod", false).is_empty());
+        assert!(Query::parse("user:", &MatchOptions::default(), false).is_err());
+    }
+
+    #[test]
+    fn filters_and_text() {
+        let query = Query::parse("user:alice bash !pid=1200", &MatchOptions::default(), false).unwrap();
+        assert_eq!(query.get_text(), "bash");
+        assert_eq!(matching("user:alice bash !pid=1200", false), [1201, 1350]);
+        assert_eq!(matching("pid:2000.. pid:..2001", false), [2000, 2001]);
+        assert_eq!(matching("state:Z", false), [1201]);
+        assert_eq!(matching("name:~^node$", false), [4200]);
+        assert!(matching("!bash", false).iter().all(|pid| ![1200, 1201, 1350].contains(pid)));
+    }
+
+    #[test]
+    fn bad_filters_are_errors() {
+        for input in ["!", "pid:abc", "pid:5..x", "name:~(", "mem>>1"] {
+            assert!(Query::parse(input, &MatchOptions::default(), false).is_err(), "{}", input);
+        }
+    }
+}
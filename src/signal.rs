@@ -0,0 +1,118 @@
+use std::{fmt, str::FromStr};
+
+// Signal numbers differ between platforms, so take them from libc where we
+// can. Windows has no signals at all, taskkill ends the process regardless
+// of which one was picked, so the numbers there are only for display.
+#[cfg(unix)]
+mod numbers {
+    pub const HUP: i32 = libc::SIGHUP;
+    pub const INT: i32 = libc::SIGINT;
+    pub const KILL: i32 = libc::SIGKILL;
+    pub const USR1: i32 = libc::SIGUSR1;
+    pub const USR2: i32 = libc::SIGUSR2;
+    pub const TERM: i32 = libc::SIGTERM;
+    pub const CONT: i32 = libc::SIGCONT;
+    pub const STOP: i32 = libc::SIGSTOP;
+}
+
+#[cfg(not(unix))]
+mod numbers {
+    pub const HUP: i32 = 1;
+    pub const INT: i32 = 2;
+    pub const KILL: i32 = 9;
+    pub const USR1: i32 = 10;
+    pub const USR2: i32 = 12;
+    pub const TERM: i32 = 15;
+    pub const CONT: i32 = 18;
+    pub const STOP: i32 = 19;
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Signal(i32);
+
+impl Signal {
+    pub const HUP: Signal = Signal(numbers::HUP);
+    pub const INT: Signal = Signal(numbers::INT);
+    pub const KILL: Signal = Signal(numbers::KILL);
+    pub const USR1: Signal = Signal(numbers::USR1);
+    pub const USR2: Signal = Signal(numbers::USR2);
+    pub const TERM: Signal = Signal(numbers::TERM);
+    pub const CONT: Signal = Signal(numbers::CONT);
+    pub const STOP: Signal = Signal(numbers::STOP);
+
+    // The signals offered in the signal picker, in the order they are shown
+    pub const COMMON: [Signal; 8] = [
+        Self::TERM,
+        Self::INT,
+        Self::HUP,
+        Self::KILL,
+        Self::STOP,
+        Self::CONT,
+        Self::USR1,
+        Self::USR2,
+    ];
+
+    const NAMES: [(Signal, &'static str); 8] = [
+        (Self::HUP, "HUP"),
+        (Self::INT, "INT"),
+        (Self::KILL, "KILL"),
+        (Self::USR1, "USR1"),
+        (Self::USR2, "USR2"),
+        (Self::TERM, "TERM"),
+        (Self::CONT, "CONT"),
+        (Self::STOP, "STOP"),
+    ];
+
+    pub fn from_number(number: i32) -> Option<Self> {
+        (1..=64).contains(&number).then_some(Self(number))
+    }
+
+    pub fn number(self) -> i32 {
+        self.0
+    }
+
+    pub fn name(self) -> Option<&'static str> {
+        Self::NAMES
+            .iter()
+            .find(|&&(sig, _)| sig == self)
+            .map(|&(_, name)| name)
+    }
+
+    // Whether the process is gone once it receives this signal, no matter
+    // what handlers it installed
+    pub fn always_ends_process(self) -> bool {
+        self == Self::KILL
+    }
+}
+
+impl fmt::Display for Signal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.name() {
+            Some(name) => write!(f, "SIG{}", name),
+            None => write!(f, "signal {}", self.0),
+        }
+    }
+}
+
+impl FromStr for Signal {
+    type Err = String;
+
+    // Accepts a number or a name with or without the SIG prefix, e.g. 15, TERM or sigterm
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+
+        if let Ok(number) = s.parse::<i32>() {
+            return Self::from_number(number)
+                .ok_or_else(|| format!("signal number {} is out of range", number));
+        }
+
+        let upper = s.to_ascii_uppercase();
+        let name = upper.strip_prefix("SIG").unwrap_or(&upper);
+
+        Self::NAMES
+            .iter()
+            .find(|&&(_, n)| n == name)
+            .map(|&(sig, _)| sig)
+            .ok_or_else(|| format!("unknown signal '{}'", s))
+    }
+}
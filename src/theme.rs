@@ -0,0 +1,364 @@
+use std::{collections::BTreeMap, env, fs, path::Path, str::FromStr};
+
+use clap::ValueEnum;
+use ratatui::style::{Color, Modifier, Style};
+use serde::Deserialize;
+
+use crate::config::Config;
+
+// Every color the interface draws with
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Theme {
+    pub background: Color,
+    pub text: Color,
+    pub border: Color,
+    // The process the pointer is on and the chosen row of popups
+    pub highlight: Color,
+    // Column and popup titles
+    pub header: Color,
+    // Marked processes
+    pub selected: Color,
+    // The keybind footer and kill progress
+    pub status: Color,
+    pub error: Color,
+    pub states: StateColors,
+}
+
+// Process rows are drawn in the color of their state, see Process::get_state
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StateColors {
+    pub running: Color,
+    pub sleeping: Color,
+    pub disk_sleep: Color,
+    pub stopped: Color,
+    pub zombie: Color,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BuiltinTheme {
+    Dark,
+    Light,
+    Solarized,
+    HighContrast,
+    NoColor,
+}
+
+impl BuiltinTheme {
+    pub fn theme(self) -> Theme {
+        match self {
+            BuiltinTheme::Dark => Theme {
+                background: Color::Rgb(0x12, 0x12, 0x12),
+                text: Color::White,
+                border: Color::Rgb(0x3a, 0x3a, 0x3a),
+                highlight: Color::LightBlue,
+                header: Color::White,
+                selected: Color::LightYellow,
+                status: Color::Gray,
+                error: Color::Red,
+                states: StateColors {
+                    running: Color::LightGreen,
+                    sleeping: Color::White,
+                    disk_sleep: Color::LightMagenta,
+                    stopped: Color::Yellow,
+                    zombie: Color::LightRed,
+                },
+            },
+            BuiltinTheme::Light => Theme {
+                background: Color::Rgb(0xfa, 0xfa, 0xfa),
+                text: Color::Rgb(0x20, 0x20, 0x20),
+                border: Color::Rgb(0xc0, 0xc0, 0xc0),
+                highlight: Color::Rgb(0x00, 0x5f, 0xd7),
+                header: Color::Rgb(0x20, 0x20, 0x20),
+                selected: Color::Rgb(0xaf, 0x5f, 0x00),
+                status: Color::Rgb(0x5f, 0x5f, 0x5f),
+                error: Color::Rgb(0xd7, 0x00, 0x00),
+                states: StateColors {
+                    running: Color::Rgb(0x00, 0x87, 0x00),
+                    sleeping: Color::Rgb(0x20, 0x20, 0x20),
+                    disk_sleep: Color::Rgb(0x87, 0x00, 0xaf),
+                    stopped: Color::Rgb(0xaf, 0x87, 0x00),
+                    zombie: Color::Rgb(0xd7, 0x00, 0x00),
+                },
+            },
+            // Ethan Schoonover's palette, base03 background with base0 text
+            BuiltinTheme::Solarized => Theme {
+                background: Color::Rgb(0x00, 0x2b, 0x36),
+                text: Color::Rgb(0x83, 0x94, 0x96),
+                border: Color::Rgb(0x58, 0x6e, 0x75),
+                highlight: Color::Rgb(0x26, 0x8b, 0xd2),
+                header: Color::Rgb(0x93, 0xa1, 0xa1),
+                selected: Color::Rgb(0xd3, 0x36, 0x82),
+                status: Color::Rgb(0x2a, 0xa1, 0x98),
+                error: Color::Rgb(0xdc, 0x32, 0x2f),
+                states: StateColors {
+                    running: Color::Rgb(0x85, 0x99, 0x00),
+                    sleeping: Color::Rgb(0x83, 0x94, 0x96),
+                    disk_sleep: Color::Rgb(0x6c, 0x71, 0xc4),
+                    stopped: Color::Rgb(0xb5, 0x89, 0x00),
+                    zombie: Color::Rgb(0xcb, 0x4b, 0x16),
+                },
+            },
+            BuiltinTheme::HighContrast => Theme {
+                background: Color::Black,
+                text: Color::White,
+                border: Color::White,
+                highlight: Color::LightYellow,
+                header: Color::LightCyan,
+                selected: Color::LightMagenta,
+                status: Color::White,
+                error: Color::LightRed,
+                states: StateColors {
+                    running: Color::LightGreen,
+                    sleeping: Color::White,
+                    disk_sleep: Color::LightMagenta,
+                    stopped: Color::LightYellow,
+                    zombie: Color::LightRed,
+                },
+            },
+            // The terminal's own colors, the highlighted row is reversed instead
+            BuiltinTheme::NoColor => Theme {
+                background: Color::Reset,
+                text: Color::Reset,
+                border: Color::Reset,
+                highlight: Color::Reset,
+                header: Color::Reset,
+                selected: Color::Reset,
+                status: Color::Reset,
+                error: Color::Reset,
+                states: StateColors {
+                    running: Color::Reset,
+                    sleeping: Color::Reset,
+                    disk_sleep: Color::Reset,
+                    stopped: Color::Reset,
+                    zombie: Color::Reset,
+                },
+            },
+        }
+    }
+
+    fn name(self) -> String {
+        self.to_possible_value()
+            .map(|value| value.get_name().to_string())
+            .unwrap_or_default()
+    }
+}
+
+// A custom theme file, every color is optional and falls back to the theme
+// named by base:
+//
+//     base = "dark"
+//     background = "#1e1e2e"
+//     highlight = "lightcyan"
+//
+//     [states]
+//     zombie = "#f38ba8"
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+struct ThemeFile {
+    base: Option<String>,
+    background: Option<String>,
+    text: Option<String>,
+    border: Option<String>,
+    highlight: Option<String>,
+    header: Option<String>,
+    selected: Option<String>,
+    status: Option<String>,
+    error: Option<String>,
+    states: BTreeMap<String, String>,
+}
+
+impl Theme {
+    // name is a built-in theme, a theme file in the themes directory next to
+    // the config file, or the path of a theme file. Without one NO_COLOR
+    // picks no-color, see https://no-color.org
+    pub fn load(name: Option<&str>) -> Result<Self, String> {
+        let Some(name) = name else {
+            let no_color = env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty());
+            return Ok(if no_color { BuiltinTheme::NoColor } else { BuiltinTheme::Dark }.theme());
+        };
+
+        if let Ok(builtin) = BuiltinTheme::from_str(name, true) {
+            return Ok(builtin.theme());
+        }
+
+        let named = Config::default_dir()
+            .map(|dir| dir.join("themes").join(format!("{}.toml", name)))
+            .filter(|path| path.is_file());
+        match named {
+            Some(path) => Self::from_file(&path),
+            None if Path::new(name).is_file() => Self::from_file(Path::new(name)),
+            None => Err(format!(
+                "unknown theme '{}', expected a theme file or one of {}",
+                name,
+                Self::builtin_names().join(", ")
+            )),
+        }
+    }
+
+    fn from_file(path: &Path) -> Result<Self, String> {
+        let contents = fs::read_to_string(path)
+            .map_err(|err| format!("{}: {}", path.display(), err))?;
+        let file: ThemeFile = toml::from_str(&contents)
+            .map_err(|err| format!("{}: {}", path.display(), err.to_string().trim_end()))?;
+
+        file.apply()
+            .map_err(|err| format!("{}: {}", path.display(), err))
+    }
+
+    pub fn builtin_names() -> Vec<String> {
+        BuiltinTheme::value_variants()
+            .iter()
+            .map(|theme| theme.name())
+            .collect()
+    }
+
+    pub fn state_color(&self, state: char) -> Color {
+        match state {
+            'R' => self.states.running,
+            'S' | 'I' => self.states.sleeping,
+            'D' => self.states.disk_sleep,
+            'T' | 't' => self.states.stopped,
+            'Z' | 'X' => self.states.zombie,
+            _ => self.text,
+        }
+    }
+
+    // Swaps in another background. If the text wouldn't show up on it, like
+    // white on white, the other colors come from whichever of the dark and
+    // light themes suits it
+    pub fn set_background(&mut self, background: Color) {
+        if let (Some(light_background), Some(light_text)) = (is_light(background), is_light(self.text)) {
+            if light_background == light_text {
+                *self = if light_background { BuiltinTheme::Light } else { BuiltinTheme::Dark }.theme();
+            }
+        }
+        self.background = background;
+    }
+
+    // Without a color of its own the highlighted row would look like every
+    // other row, so it is reversed instead
+    pub fn highlight_style(&self) -> Style {
+        if self.highlight == Color::Reset {
+            Style::default().add_modifier(Modifier::REVERSED)
+        } else {
+            Style::default().fg(self.highlight)
+        }
+    }
+}
+
+impl ThemeFile {
+    fn apply(self) -> Result<Theme, String> {
+        let base = match &self.base {
+            Some(base) => BuiltinTheme::from_str(base, true).map_err(|_| format!(
+                "unknown base theme '{}', expected one of {}",
+                base,
+                Theme::builtin_names().join(", ")
+            ))?,
+            None => BuiltinTheme::Dark,
+        };
+        let mut theme = base.theme();
+
+        let colors = [
+            ("background", self.background, &mut theme.background),
+            ("text", self.text, &mut theme.text),
+            ("border", self.border, &mut theme.border),
+            ("highlight", self.highlight, &mut theme.highlight),
+            ("header", self.header, &mut theme.header),
+            ("selected", self.selected, &mut theme.selected),
+            ("status", self.status, &mut theme.status),
+            ("error", self.error, &mut theme.error),
+        ];
+        for (key, value, color) in colors {
+            if let Some(value) = value {
+                *color = parse_color(&value).map_err(|err| format!("'{}': {}", key, err))?;
+            }
+        }
+
+        for (state, value) in self.states {
+            let color = match state.as_str() {
+                "running" => &mut theme.states.running,
+                "sleeping" => &mut theme.states.sleeping,
+                "disk-sleep" => &mut theme.states.disk_sleep,
+                "stopped" => &mut theme.states.stopped,
+                "zombie" => &mut theme.states.zombie,
+                _ => return Err(format!(
+                    "unknown state '{}', expected one of running, sleeping, disk-sleep, stopped, zombie",
+                    state
+                )),
+            };
+            *color = parse_color(&value).map_err(|err| format!("'states.{}': {}", state, err))?;
+        }
+        Ok(theme)
+    }
+}
+
+// None for colors that depend on the terminal
+fn is_light(color: Color) -> Option<bool> {
+    match color {
+        Color::Rgb(r, g, b) => Some(0.2126 * r as f32 + 0.7152 * g as f32 + 0.0722 * b as f32 > 127.5),
+        Color::White | Color::Gray | Color::Yellow | Color::Cyan
+            | Color::LightGreen | Color::LightYellow | Color::LightCyan => Some(true),
+        Color::Black | Color::DarkGray | Color::Red | Color::Green | Color::Blue | Color::Magenta
+            | Color::LightRed | Color::LightBlue | Color::LightMagenta => Some(false),
+        _ => None,
+    }
+}
+
+// A color name like lightblue, #rrggbb, or the red, green and blue values
+// separated by commas, either in decimal or 0x prefixed hex
+pub fn parse_color(value: &str) -> Result<Color, String> {
+    let invalid = || format!("invalid color '{}', see --show-colors", value);
+
+    if value.contains(',') {
+        let codes = value
+            .split(',')
+            .map(|code| {
+                let code = code.trim();
+                match code.strip_prefix("0x") {
+                    Some(hex) => u8::from_str_radix(hex, 16),
+                    None => code.parse::<u8>(),
+                }
+            })
+            .collect::<Result<Vec<u8>, _>>()
+            .map_err(|_| invalid())?;
+
+        return match codes.as_slice() {
+            &[r, g, b] => Ok(Color::Rgb(r, g, b)),
+            _ => Err(invalid()),
+        };
+    }
+
+    match value.trim() {
+        "default" => Ok(Color::Reset),
+        value => Color::from_str(value).map_err(|_| invalid()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn background_keeps_text_readable() {
+        let mut theme = BuiltinTheme::Dark.theme();
+        theme.set_background(Color::White);
+        assert_eq!(theme.background, Color::White);
+        assert_eq!(theme.text, BuiltinTheme::Light.theme().text);
+
+        let mut theme = BuiltinTheme::Dark.theme();
+        theme.set_background(Color::Rgb(0, 0, 0x40));
+        assert_eq!(theme.text, Color::White);
+        assert_eq!(theme.highlight, Color::LightBlue);
+    }
+
+    #[test]
+    fn parse_colors() {
+        assert_eq!(parse_color("lightblue"), Ok(Color::LightBlue));
+        assert_eq!(parse_color("#1e1e2e"), Ok(Color::Rgb(0x1e, 0x1e, 0x2e)));
+        assert_eq!(parse_color("30, 0x1e,46"), Ok(Color::Rgb(30, 30, 46)));
+        assert_eq!(parse_color(" default "), Ok(Color::Reset));
+        for value in ["notacolor", "1,2", "1,2,3,4", "256,0,0", "0xzz,0,0"] {
+            assert!(parse_color(value).is_err(), "{}", value);
+        }
+    }
+}
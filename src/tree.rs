@@ -0,0 +1,263 @@
+use std::{cmp::Ordering, collections::{HashMap, HashSet}};
+
+use crate::interface::{Process, ProcessId};
+
+// One line of the tree view, the process list flattened depth first
+pub struct TreeRow {
+    pub proc: Process,
+    // Indentation guides and the expand marker that go before the command
+    pub prefix: String,
+    pub has_children: bool,
+    // Totals for the process and everything below it, windows doesn't
+    // report a usable number for either so they are always 0 there
+    #[cfg_attr(target_os = "windows", allow(dead_code))]
+    pub subtree_mem: f32,
+    #[cfg_attr(target_os = "windows", allow(dead_code))]
+    pub subtree_cpu: f32,
+}
+
+struct Builder<'a> {
+    children: HashMap<u64, Vec<&'a Process>>,
+    visible: Option<&'a HashSet<ProcessId>>,
+    collapsed: &'a HashSet<ProcessId>,
+    rows: Vec<TreeRow>,
+}
+
+// Nests procs under their parents. When visible is given only those processes
+// are shown, and collapsed subtrees are expanded so every one of them can be
+// seen. Otherwise everything is shown except the children of collapsed nodes.
+// Siblings are ordered with compare.
+pub fn build_rows<F>(procs: &[Process], visible: Option<&HashSet<ProcessId>>, collapsed: &HashSet<ProcessId>, compare: F) -> Vec<TreeRow>
+where
+    F: Fn(&Process, &Process) -> Ordering
+{
+    let pids = procs
+        .iter()
+        .map(|proc| proc.get_pid())
+        .collect::<HashSet<u64>>();
+
+    let mut children: HashMap<u64, Vec<&Process>> = HashMap::new();
+    let mut roots = Vec::new();
+    procs.iter().for_each(|proc| {
+        if proc.get_ppid() != proc.get_pid() && pids.contains(&proc.get_ppid()) {
+            children.entry(proc.get_ppid()).or_default().push(proc);
+        } else {
+            roots.push(proc);
+        }
+    });
+
+    roots.sort_by(|a, b| compare(a, b));
+    children
+        .values_mut()
+        .for_each(|kids| kids.sort_by(|a, b| compare(a, b)));
+
+    let mut builder = Builder {
+        children,
+        visible,
+        collapsed,
+        rows: Vec::new(),
+    };
+    let roots = roots
+        .into_iter()
+        .filter(|proc| builder.is_visible(proc))
+        .collect::<Vec<&Process>>();
+    let last = roots.len().saturating_sub(1);
+
+    roots.iter().enumerate().for_each(|(i, proc)| {
+        builder.add(proc, "", i == last, true);
+    });
+    builder.rows
+}
+
+// Every process in matches plus all of their ancestors, so matches in the
+// tree view are shown in place under their parents
+pub fn with_ancestors(procs: &[Process], matches: &[Process]) -> HashSet<ProcessId> {
+    let by_pid = procs
+        .iter()
+        .map(|proc| (proc.get_pid(), proc))
+        .collect::<HashMap<u64, &Process>>();
+
+    let mut visible = HashSet::new();
+    matches.iter().for_each(|proc| {
+        let mut current = by_pid.get(&proc.get_pid()).copied();
+        while let Some(p) = current {
+            // Already walked up from here
+            if !visible.insert(p.get_id()) {
+                break;
+            }
+            current = if p.get_ppid() != p.get_pid() {
+                by_pid.get(&p.get_ppid()).copied()
+            } else {
+                None
+            };
+        }
+    });
+    visible
+}
+
+impl Builder<'_> {
+    fn is_visible(&self, proc: &Process) -> bool {
+        self.visible.is_none_or(|visible| visible.contains(&proc.get_id()))
+    }
+
+    // Adds proc and whatever is shown below it, returning the subtree totals
+    // for memory and cpu
+    fn add(&mut self, proc: &Process, indent: &str, is_last: bool, is_root: bool) -> (f32, f32) {
+        let kids = self.children
+            .get(&proc.get_pid())
+            .cloned()
+            .unwrap_or_default();
+        let has_children = !kids.is_empty();
+        // Searching shows every match no matter what is collapsed
+        let collapsed = self.visible.is_none() && self.collapsed.contains(&proc.get_id());
+
+        let marker = match (has_children, collapsed) {
+            (false, _) => "  ",
+            (true, true) => "\u{25b8} ",
+            (true, false) => "\u{25be} ",
+        };
+        let prefix = if is_root {
+            marker.to_string()
+        } else if is_last {
+            format!("{}\u{2514}\u{2500}{}", indent, marker)
+        } else {
+            format!("{}\u{251c}\u{2500}{}", indent, marker)
+        };
+        let child_indent = if is_root {
+            String::new()
+        } else if is_last {
+            format!("{}  ", indent)
+        } else {
+            format!("{}\u{2502} ", indent)
+        };
+
+        let spot = self.rows.len();
+        self.rows.push(TreeRow {
+            proc: proc.clone(),
+            prefix,
+            has_children,
+            subtree_mem: 0.0,
+            subtree_cpu: 0.0,
+        });
+
+        let (shown, hidden): (Vec<&Process>, Vec<&Process>) = kids
+            .into_iter()
+            .partition(|kid| !collapsed && self.is_visible(kid));
+        let last = shown.len().saturating_sub(1);
+
+        // Hidden children still count towards the totals
+        let (mut mem, mut cpu) = hidden
+            .iter()
+            .fold(usage(proc), |(mem, cpu), kid| {
+                let (kid_mem, kid_cpu) = self.subtree_totals(kid);
+                (mem + kid_mem, cpu + kid_cpu)
+            });
+        for (i, kid) in shown.iter().enumerate() {
+            let (kid_mem, kid_cpu) = self.add(kid, &child_indent, i == last, false);
+            mem += kid_mem;
+            cpu += kid_cpu;
+        }
+
+        self.rows[spot].subtree_mem = mem;
+        self.rows[spot].subtree_cpu = cpu;
+        (mem, cpu)
+    }
+
+    fn subtree_totals(&self, proc: &Process) -> (f32, f32) {
+        let own = usage(proc);
+
+        self.children
+            .get(&proc.get_pid())
+            .map(|kids| {
+                kids.iter()
+                    .fold(own, |(mem, cpu), kid| {
+                        let (kid_mem, kid_cpu) = self.subtree_totals(kid);
+                        (mem + kid_mem, cpu + kid_cpu)
+                    })
+            })
+            .unwrap_or(own)
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+fn usage(proc: &Process) -> (f32, f32) {
+    (proc.get_mem(), proc.get_cpu())
+}
+
+#[cfg(target_os = "windows")]
+fn usage(_proc: &Process) -> (f32, f32) {
+    (0.0, 0.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interface::{FixtureSource, ProcessSource};
+
+    fn procs() -> Vec<Process> {
+        FixtureSource::parse(include_str!("../fixtures/sample.tsv"))
+            .unwrap()
+            .enumerate()
+            .unwrap()
+            .procs
+    }
+
+    fn rows(procs: &[Process], visible: Option<&HashSet<ProcessId>>, collapsed: &HashSet<ProcessId>) -> Vec<TreeRow> {
+        build_rows(procs, visible, collapsed, |a, b| a.get_pid().cmp(&b.get_pid()))
+    }
+
+    fn pids(rows: &[TreeRow]) -> Vec<u64> {
+        rows.iter().map(|row| row.proc.get_pid()).collect()
+    }
+
+    fn id(procs: &[Process], pid: u64) -> ProcessId {
+        procs.iter().find(|proc| proc.get_pid() == pid).unwrap().get_id()
+    }
+
+    #[test]
+    fn nests_under_parents() {
+        let procs = procs();
+        let rows = rows(&procs, None, &HashSet::new());
+        assert_eq!(pids(&rows), [1, 1200, 1201, 1350, 4200, 4201, 5000, 2000, 2001, 3100]);
+
+        let prefixes = rows.iter().map(|row| row.prefix.as_str()).collect::<Vec<&str>>();
+        assert_eq!(prefixes[..5], ["\u{25be} ", "\u{251c}\u{2500}\u{25be} ", "\u{2502} \u{251c}\u{2500}  ", "\u{2502} \u{251c}\u{2500}\u{25be} ", "\u{2502} \u{2502} \u{251c}\u{2500}  "]);
+        assert_eq!(prefixes[9], "\u{2514}\u{2500}  ");
+        assert!(rows[0].has_children && !rows[2].has_children);
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "macos"))]
+    #[test]
+    fn subtree_totals() {
+        let procs = procs();
+        let rows = rows(&procs, None, &HashSet::new());
+        let bash = rows.iter().find(|row| row.proc.get_pid() == 1200).unwrap();
+        assert!((bash.subtree_mem - 6.4).abs() < 0.001);
+        assert!((bash.subtree_cpu - 38.6).abs() < 0.001);
+    }
+
+    #[test]
+    fn collapsed_hides_children() {
+        let procs = procs();
+        let collapsed = HashSet::from([id(&procs, 1350)]);
+        let rows = rows(&procs, None, &collapsed);
+        assert_eq!(pids(&rows), [1, 1200, 1201, 1350, 5000, 2000, 2001, 3100]);
+        assert!(rows[3].prefix.ends_with("\u{25b8} "));
+
+        // Hidden children still count
+        #[cfg(any(target_os = "linux", target_os = "macos"))]
+        assert!((rows[3].subtree_cpu - 13.6).abs() < 0.001);
+    }
+
+    #[test]
+    fn search_shows_matches_and_ancestors() {
+        let procs = procs();
+        let nodemon = procs.iter().filter(|proc| proc.get_pid() == 4201).cloned().collect::<Vec<Process>>();
+        let visible = with_ancestors(&procs, &nodemon);
+        assert_eq!(visible.len(), 4);
+
+        // Collapsing doesn't hide matches
+        let collapsed = HashSet::from([id(&procs, 1350)]);
+        assert_eq!(pids(&rows(&procs, Some(&visible), &collapsed)), [1, 1200, 1350, 4201]);
+    }
+}
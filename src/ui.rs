@@ -1,37 +1,32 @@
-use ratatui::{layout::{Constraint, Direction, Flex, Layout, Rect}, style::{Color, Style}, text::Line, widgets::{Block, Borders, Paragraph}, Frame};
+use std::time::Duration;
+
+use crate::interface::{Process, ProcessDetails};
+use crate::theme::Theme;
+
+use ratatui::{layout::{Constraint, Flex, Layout, Rect}, style::{Modifier, Style}, text::{Line, Span}, widgets::{Block, Borders, Clear, Paragraph, Wrap}, Frame};
 
 pub struct Ui;
 
 impl Ui {
-    pub fn show_help<'a>(frame: &mut Frame<'a>, keybinds_text: &Vec<&str>, tc: Color, bgc: Color) {
+    pub fn show_help<'a>(frame: &mut Frame<'a>, keybinds_text: &[String], theme: &Theme) {
         frame.render_widget(
             Paragraph::new("")
                 .block(
-                    Self::generate_block(
-                        String::from("Help"),
-                        Some(tc),
-                        Some(bgc)
-                    )
+                    Self::generate_block(String::from("Help"), theme)
             ),
             frame.area()
         );
 
-        let style = Style::default().fg(tc);
+        let style = Style::default().fg(theme.text);
                     
-        let mut help_text = keybinds_text.clone().iter()
-            .map(|&l| {
-                if l == "[ctrl+h] help" {
-                    Line::styled("[ctrl+h] to exit this screen", style.clone())
-                } else {
-                    Line::styled(l, style.clone())
-                }
-            })
+        let mut help_text = keybinds_text.iter()
+            .map(|l| Line::styled(l.as_str(), style))
             .collect::<Vec<Line>>();
         help_text.extend_from_slice(
             &[
-                Line::styled("[ctrl+r] reset scroll", style.clone()),
-                Line::styled("[\u{2195}] use arrow keys or mouse to move up and down", style.clone()),
+                Line::styled("Scroll with the mouse, click a column title to sort by it", style),
                 Line::styled("Enter characters to fuzzy search for processes", style),
+                Line::styled("Filter with fields, e.g. user:root cpu>20 pid:1..100 name:~^node !bash", style),
             ]
         );
 
@@ -45,6 +40,247 @@ impl Ui {
         );
     }
         
+    pub fn show_signal_picker<'a>(frame: &mut Frame<'a>, title: String, options: &[String], selected: usize, theme: &Theme) {
+        let lines = options.iter()
+            .enumerate()
+            .map(|(i, option)| {
+                if i == selected {
+                    Line::styled(format!("> {}", option), theme.highlight_style())
+                } else {
+                    Line::styled(format!("  {}", option), Style::default().fg(theme.text))
+                }
+            })
+            .collect::<Vec<Line>>();
+
+        let width = lines.iter()
+            .map(|l| l.width())
+            .chain(std::iter::once(title.len()))
+            .max()
+            .unwrap_or(0) as u16 + 4;
+        let area = Self::center_rect(frame.area(),
+            Constraint::Length(width),
+            Constraint::Length(lines.len() as u16 + 2));
+
+        frame.render_widget(Clear, area);
+        frame.render_widget(
+            Paragraph::new(lines)
+                .block(Self::generate_block(title, theme)),
+            area
+        );
+    }
+
+    // Progress of SIGTERM then SIGKILL escalations, in the bottom right corner of the given area
+    pub fn show_escalations<'a>(frame: &mut Frame<'a>, area: Rect, lines: &[String], theme: &Theme) {
+        let width = lines.iter()
+            .map(|l| l.chars().count())
+            .max()
+            .unwrap_or(0) as u16 + 2;
+        let width = width.min(area.width);
+        let height = (lines.len() as u16 + 2).min(area.height);
+        let rect = Rect::new(
+            area.x + area.width - width,
+            area.y + area.height - height,
+            width,
+            height
+        );
+
+        frame.render_widget(Clear, rect);
+        frame.render_widget(
+            Paragraph::new(
+                lines.iter()
+                    .map(|l| Line::styled(l.as_str(), Style::default().fg(theme.status)))
+                    .collect::<Vec<Line>>()
+            )
+            .block(Self::generate_block(String::from("Kill Progress"), theme)),
+            rect
+        );
+    }
+
+    // Lists the processes that are about to be killed and waits for y or n
+    pub fn show_confirmation<'a>(frame: &mut Frame<'a>, title: String, procs: &[Process], theme: &Theme) {
+        let style = Style::default().fg(theme.text);
+        let pid_width = procs.iter()
+            .map(|p| p.get_pid().max(p.get_ppid()).to_string().len())
+            .chain(std::iter::once(4))
+            .max()
+            .unwrap_or(4);
+        let user_width = procs.iter()
+            .map(|p| p.get_user().chars().count())
+            .chain(std::iter::once(4))
+            .max()
+            .unwrap_or(4);
+
+        // Leave room for the borders, the column header and the footer
+        let max_rows = (frame.area().height as usize).saturating_sub(8).max(1);
+
+        let mut lines = vec![
+            Line::styled(format!("{:>pid_width$}  {:>pid_width$}  {:<user_width$}  COMMAND", "PID", "PPID", "USER"), Style::default().fg(theme.header)),
+        ];
+        lines.extend(
+            procs.iter()
+                .take(max_rows)
+                .map(|p| Line::styled(
+                    format!("{:>pid_width$}  {:>pid_width$}  {:<user_width$}  {}", p.get_pid(), p.get_ppid(), p.get_user(), p.get_command()),
+                    style
+                ))
+        );
+        if procs.len() > max_rows {
+            lines.push(Line::styled(format!("\u{2026} and {} more", procs.len() - max_rows), style));
+        }
+        lines.push(Line::styled("", style));
+        lines.push(Line::styled("[y|enter] confirm  [n|esc] cancel", theme.highlight_style()));
+
+        let width = lines.iter()
+            .map(|l| l.width())
+            .chain(std::iter::once(title.len()))
+            .max()
+            .unwrap_or(0) as u16 + 4;
+        let area = Self::center_rect(frame.area(),
+            Constraint::Length(width),
+            Constraint::Length(lines.len() as u16 + 2));
+
+        frame.render_widget(Clear, area);
+        frame.render_widget(
+            Paragraph::new(lines)
+                .block(Self::generate_block(title, theme)),
+            area
+        );
+    }
+
+    // Everything known about the highlighted process, None if nothing is
+    // highlighted. environment_key is what shows the environment
+    pub fn show_details<'a>(frame: &mut Frame<'a>, area: Rect, details: Option<&Result<ProcessDetails, String>>, show_environment: bool, environment_key: Option<String>, theme: &Theme) {
+        let text = Style::default().fg(theme.text);
+        let row = |label: &str, value: String| Line::from(vec![
+            Span::styled(format!("{:<13}", label), Style::default().fg(theme.header)),
+            Span::styled(value, text),
+        ]);
+        let unknown = || String::from("-");
+
+        let (title, lines) = match details {
+            None => (String::from("Details"), vec![Line::styled("No process selected", text)]),
+            Some(Err(err)) => (
+                String::from("Details"),
+                vec![Line::styled(format!("Couldn't read the details: {}", err), Style::default().fg(theme.error))]
+            ),
+            Some(Ok(details)) => {
+                let parents = details.parents
+                    .iter()
+                    .map(|(pid, command)| format!("{} ({})", command, pid))
+                    .collect::<Vec<String>>()
+                    .join(" \u{2190} ");
+                let started = match (&details.start_time, details.running_for) {
+                    (Some(start_time), Some(running_for)) => format!("{} ({} ago)", start_time, Self::format_duration(running_for)),
+                    (None, Some(running_for)) => format!("{} ago", Self::format_duration(running_for)),
+                    (Some(start_time), None) => start_time.clone(),
+                    (None, None) => unknown(),
+                };
+                let priority = match (details.priority, details.nice) {
+                    (Some(priority), Some(nice)) => format!("{} (nice {})", priority, nice),
+                    (priority, nice) => priority.or(nice).map(|n| n.to_string()).unwrap_or_else(unknown),
+                };
+                let memory = match (details.rss, details.vsz) {
+                    (Some(rss), Some(vsz)) => format!("{} resident, {} virtual", Self::format_bytes(rss), Self::format_bytes(vsz)),
+                    _ => unknown(),
+                };
+                let user = match &details.group {
+                    Some(group) => format!("{} / {}", details.user, group),
+                    None => details.user.clone(),
+                };
+
+                let mut lines = vec![
+                    row("Executable", details.exe.clone().unwrap_or_else(unknown)),
+                    row("Directory", details.cwd.clone().unwrap_or_else(unknown)),
+                    row("User/group", user),
+                    row("Parents", if parents.is_empty() { unknown() } else { parents }),
+                    row("Started", started),
+                    row("State", format!("{} ({})", details.state, details.state_name())),
+                    row("Threads", details.threads.map(|n| n.to_string()).unwrap_or_else(unknown)),
+                    row("Priority", priority),
+                    row("Memory", memory),
+                    row("Open files", details.open_files.map(|n| n.to_string()).unwrap_or_else(unknown)),
+                    row("Cgroup", details.cgroups.as_ref().map(|cgroups| cgroups.join(", ")).unwrap_or_else(unknown)),
+                    // Last before the environment since it can run over several lines
+                    row("Command line", details.cmdline.clone()),
+                ];
+
+                match &details.environ {
+                    Some(environ) if show_environment => {
+                        lines.push(row("Environment", format!("{} variables", environ.len())));
+                        lines.extend(environ.iter().map(|var| Line::styled(format!("  {}", var), text)));
+                    },
+                    Some(environ) => {
+                        let hint = environment_key
+                            .map(|key| format!(", {} to show", key))
+                            .unwrap_or_default();
+                        lines.push(row("Environment", format!("{} variables{}", environ.len(), hint)));
+                    },
+                    None => lines.push(row("Environment", unknown())),
+                }
+
+                (format!("Details: {} ({})", details.command, details.pid), lines)
+            },
+        };
+
+        frame.render_widget(
+            Paragraph::new(lines)
+                .wrap(Wrap { trim: false })
+                .block(Self::generate_block(title, theme)),
+            area
+        );
+    }
+
+    // Like 12.3 MiB
+    fn format_bytes(bytes: u64) -> String {
+        let units = ["B", "KiB", "MiB", "GiB", "TiB"];
+        let mut value = bytes as f64;
+        let mut unit = 0;
+        while value >= 1024.0 && unit < units.len() - 1 {
+            value /= 1024.0;
+            unit += 1;
+        }
+
+        if unit == 0 {
+            format!("{} B", bytes)
+        } else {
+            format!("{:.1} {}", value, units[unit])
+        }
+    }
+
+    // The two largest units, like 3d 4h or 5m 12s
+    fn format_duration(duration: Duration) -> String {
+        let secs = duration.as_secs();
+        let (days, hours, mins) = (secs / 86400, secs / 3600 % 24, secs / 60 % 60);
+
+        if days > 0 {
+            format!("{}d {}h", days, hours)
+        } else if hours > 0 {
+            format!("{}h {}m", hours, mins)
+        } else if mins > 0 {
+            format!("{}m {}s", mins, secs % 60)
+        } else {
+            format!("{}s", secs)
+        }
+    }
+
+    // text after prefix with the chars at positions emphasized, for showing
+    // where a search matched
+    pub fn highlight_matches<'a>(prefix: &str, text: &str, positions: &[usize], style: Style) -> Line<'a> {
+        let matched = style.add_modifier(Modifier::BOLD | Modifier::UNDERLINED);
+        let mut spans = vec![Span::styled(prefix.to_string(), style)];
+
+        text.chars().enumerate().for_each(|(i, c)| {
+            let c_style = if positions.contains(&i) { matched } else { style };
+            // Merge runs with the same style into one span
+            match spans.last_mut() {
+                Some(span) if span.style == c_style => span.content.to_mut().push(c),
+                _ => spans.push(Span::styled(c.to_string(), c_style)),
+            }
+        });
+
+        Line::from(spans)
+    }
+
     pub fn center_rect(area: Rect, horizontal: Constraint, vertical: Constraint) -> Rect {
         let [area] = Layout::horizontal([horizontal])
             .flex(Flex::Center)
@@ -55,18 +291,14 @@ impl Ui {
         area
     }
 
-    pub fn generate_block<'a>(name: String, title_color: Option<Color>, bg_color: Option<Color>) -> Block<'a> {
+    pub fn generate_block<'a>(name: String, theme: &Theme) -> Block<'a> {
         Block::default()
             .title(name)
             .title_alignment(ratatui::layout::Alignment::Center)
-            .title_style(Style::default().fg(
-                title_color.unwrap_or(Color::Rgb(0xff, 0xff, 0xff))
-            ))
+            .title_style(Style::default().fg(theme.header))
             .borders(Borders::ALL)
-            .border_style(Style::default().fg(Color::Rgb(0x3a, 0x3a, 0x3a)))
-            .style(Style::default().bg(
-                bg_color.unwrap_or(Color::Rgb(0x12, 0x12, 0x12))
-            ))
+            .border_style(Style::default().fg(theme.border))
+            .style(Style::default().bg(theme.background))
     }
 }
 